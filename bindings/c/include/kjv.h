@@ -0,0 +1,69 @@
+/*
+ * C interface to the parsed King James Bible.
+ *
+ * Every function returning int32_t returns one of the KJV_* status codes.
+ * Strings handed out through char** parameters are owned by the caller and
+ * must be released with kjv_string_free. All strings are UTF-8.
+ */
+#ifndef KJV_H
+#define KJV_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define KJV_OK 0
+#define KJV_ERR_NULL_ARGUMENT 1
+#define KJV_ERR_INVALID_UTF8 2
+#define KJV_ERR_IO 3
+#define KJV_ERR_DECODE 4
+#define KJV_ERR_INVALID_REFERENCE 5
+#define KJV_ERR_UNKNOWN_BOOK 6
+#define KJV_ERR_CHAPTER_OUT_OF_RANGE 7
+#define KJV_ERR_VERSE_OUT_OF_RANGE 8
+#define KJV_ERR_PANIC 9
+
+typedef struct KjvBible KjvBible;
+typedef struct KjvSearchResults KjvSearchResults;
+
+/* Loads a bible.bin written by parse-bible (plain or compressed). */
+int32_t kjv_open(const char *path, KjvBible **out_bible);
+void kjv_close(KjvBible *bible);
+
+/*
+ * Looks up "John 3:16", "John 3:16-18" or a whole chapter such as "John 3".
+ * A single verse yields its text; several verses yield one line per verse,
+ * prefixed with the verse number ("16 For God so loved...").
+ */
+int32_t kjv_lookup(const KjvBible *bible, const char *reference, char **out_text);
+
+/* Runs a search; the results stay valid after the bible is closed. */
+int32_t kjv_search(const KjvBible *bible, const char *query, KjvSearchResults **out_results);
+size_t kjv_search_count(const KjvSearchResults *results);
+/*
+ * Advances to the next hit, returning false once all hits were returned or
+ * on an error. kjv_last_error is NULL after the former and set after the
+ * latter. On true, *out_reference ("John 3:16") and *out_text must be freed.
+ */
+bool kjv_search_next(KjvSearchResults *results, char **out_reference, char **out_text);
+void kjv_search_free(KjvSearchResults *results);
+
+void kjv_string_free(char *s);
+
+/* Static description of a status code. */
+const char *kjv_status_message(int32_t status);
+/*
+ * Detailed message for the last failed call on this thread, or NULL.
+ * Valid until the next failing call, or kjv_search_next, on the same thread.
+ */
+const char *kjv_last_error(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* KJV_H */
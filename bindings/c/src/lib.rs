@@ -0,0 +1,399 @@
+// C ABI over the parsed Bible, see include/kjv.h for the interface
+
+use parse_bible::{Bible, ReferenceError, VerseRef, read_bible_from_bin};
+use std::cell::RefCell;
+use std::error::Error;
+use std::ffi::{CStr, CString, c_char};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+pub const KJV_OK: i32 = 0;
+pub const KJV_ERR_NULL_ARGUMENT: i32 = 1;
+pub const KJV_ERR_INVALID_UTF8: i32 = 2;
+pub const KJV_ERR_IO: i32 = 3;
+pub const KJV_ERR_DECODE: i32 = 4;
+pub const KJV_ERR_INVALID_REFERENCE: i32 = 5;
+pub const KJV_ERR_UNKNOWN_BOOK: i32 = 6;
+pub const KJV_ERR_CHAPTER_OUT_OF_RANGE: i32 = 7;
+pub const KJV_ERR_VERSE_OUT_OF_RANGE: i32 = 8;
+pub const KJV_ERR_PANIC: i32 = 9;
+
+pub struct KjvBible {
+    bible: Bible,
+}
+
+pub struct KjvSearchResults {
+    hits: std::vec::IntoIter<(String, String)>,
+    count: usize,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+// Status code plus the detailed message kept for kjv_last_error
+struct Failure(i32, String);
+
+impl From<ReferenceError> for Failure {
+    fn from(e: ReferenceError) -> Self {
+        let status = match e {
+            ReferenceError::InvalidReference(_) => KJV_ERR_INVALID_REFERENCE,
+            ReferenceError::UnknownBook(_) => KJV_ERR_UNKNOWN_BOOK,
+            ReferenceError::ChapterOutOfRange { .. } => KJV_ERR_CHAPTER_OUT_OF_RANGE,
+            ReferenceError::VerseOutOfRange { .. } => KJV_ERR_VERSE_OUT_OF_RANGE,
+        };
+        Failure(status, e.to_string())
+    }
+}
+
+impl From<Box<dyn Error>> for Failure {
+    fn from(e: Box<dyn Error>) -> Self {
+        let status = if e.is::<std::io::Error>() {
+            KJV_ERR_IO
+        } else {
+            KJV_ERR_DECODE
+        };
+        Failure(status, e.to_string())
+    }
+}
+
+fn set_last_error(message: String) {
+    let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|e| *e.borrow_mut() = Some(message));
+}
+
+fn clear_last_error() {
+    LAST_ERROR.with(|e| *e.borrow_mut() = None);
+}
+
+// Runs the body of an exported function, turning errors and panics into
+// status codes so nothing unwinds across the FFI boundary
+fn ffi_call(f: impl FnOnce() -> Result<(), Failure>) -> i32 {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => KJV_OK,
+        Ok(Err(Failure(status, message))) => {
+            set_last_error(message);
+            status
+        }
+        Err(_) => {
+            set_last_error("panic inside kjv".to_string());
+            KJV_ERR_PANIC
+        }
+    }
+}
+
+unsafe fn str_arg<'a>(ptr: *const c_char, name: &str) -> Result<&'a str, Failure> {
+    if ptr.is_null() {
+        return Err(Failure(KJV_ERR_NULL_ARGUMENT, format!("{} is NULL", name)));
+    }
+    // SAFETY: the caller guarantees a NUL terminated string
+    unsafe { CStr::from_ptr(ptr) }
+        .to_str()
+        .map_err(|_| Failure(KJV_ERR_INVALID_UTF8, format!("{} is not valid UTF-8", name)))
+}
+
+fn null_argument(name: &str) -> Failure {
+    Failure(KJV_ERR_NULL_ARGUMENT, format!("{} is NULL", name))
+}
+
+fn into_c_string(s: String) -> *mut c_char {
+    CString::new(s)
+        .expect("bible text contains no NUL bytes")
+        .into_raw()
+}
+
+/// # Safety
+/// `path` must be a NUL terminated string and `out_bible` a valid pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn kjv_open(path: *const c_char, out_bible: *mut *mut KjvBible) -> i32 {
+    ffi_call(|| {
+        if out_bible.is_null() {
+            return Err(null_argument("out_bible"));
+        }
+        let path = unsafe { str_arg(path, "path")? };
+        let bible = read_bible_from_bin(path)?;
+        unsafe { *out_bible = Box::into_raw(Box::new(KjvBible { bible })) };
+        Ok(())
+    })
+}
+
+/// # Safety
+/// `bible` must come from `kjv_open` (or be NULL) and not be used afterwards.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn kjv_close(bible: *mut KjvBible) {
+    if !bible.is_null() {
+        drop(unsafe { Box::from_raw(bible) });
+    }
+}
+
+/// # Safety
+/// `bible` must come from `kjv_open`, `reference` must be a NUL terminated
+/// string and `out_text` a valid pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn kjv_lookup(
+    bible: *const KjvBible,
+    reference: *const c_char,
+    out_text: *mut *mut c_char,
+) -> i32 {
+    ffi_call(|| {
+        let bible = unsafe { bible.as_ref() }.ok_or_else(|| null_argument("bible"))?;
+        if out_text.is_null() {
+            return Err(null_argument("out_text"));
+        }
+        let reference = VerseRef::parse(unsafe { str_arg(reference, "reference")? })?;
+        let verses = bible.bible.lookup(&reference)?;
+
+        let text = if reference.is_single_verse() {
+            verses[0].text.clone()
+        } else {
+            verses
+                .iter()
+                .map(|v| format!("{} {}", v.number, v.text))
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        unsafe { *out_text = into_c_string(text) };
+        Ok(())
+    })
+}
+
+/// # Safety
+/// `bible` must come from `kjv_open`, `query` must be a NUL terminated string
+/// and `out_results` a valid pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn kjv_search(
+    bible: *const KjvBible,
+    query: *const c_char,
+    out_results: *mut *mut KjvSearchResults,
+) -> i32 {
+    ffi_call(|| {
+        let bible = unsafe { bible.as_ref() }.ok_or_else(|| null_argument("bible"))?;
+        if out_results.is_null() {
+            return Err(null_argument("out_results"));
+        }
+        let query = unsafe { str_arg(query, "query")? };
+
+        let hits: Vec<(String, String)> = bible
+            .bible
+            .search(query)
+            .into_iter()
+            .map(|hit| {
+                let reference = format!("{} {}:{}", hit.book, hit.chapter, hit.verse.number);
+                (reference, hit.verse.text.clone())
+            })
+            .collect();
+        let results = KjvSearchResults {
+            count: hits.len(),
+            hits: hits.into_iter(),
+        };
+        unsafe { *out_results = Box::into_raw(Box::new(results)) };
+        Ok(())
+    })
+}
+
+/// # Safety
+/// `results` must come from `kjv_search` or be NULL.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn kjv_search_count(results: *const KjvSearchResults) -> usize {
+    unsafe { results.as_ref() }.map_or(0, |r| r.count)
+}
+
+/// # Safety
+/// `results` must come from `kjv_search`; the out pointers must be valid.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn kjv_search_next(
+    results: *mut KjvSearchResults,
+    out_reference: *mut *mut c_char,
+    out_text: *mut *mut c_char,
+) -> bool {
+    // Errors (NULL arguments or a panic) also end the iteration, with the
+    // detail left for kjv_last_error. Any other call clears it, so that
+    // callers can tell the end of the hits from a failure.
+    let mut found = false;
+    let status = ffi_call(|| {
+        let results = unsafe { results.as_mut() }.ok_or_else(|| null_argument("results"))?;
+        if out_reference.is_null() || out_text.is_null() {
+            return Err(null_argument("out_reference or out_text"));
+        }
+        if let Some((reference, text)) = results.hits.next() {
+            let (reference, text) = (into_c_string(reference), into_c_string(text));
+            unsafe {
+                *out_reference = reference;
+                *out_text = text;
+            }
+            found = true;
+        }
+        Ok(())
+    });
+    if status == KJV_OK {
+        clear_last_error();
+    }
+    status == KJV_OK && found
+}
+
+/// # Safety
+/// `results` must come from `kjv_search` (or be NULL) and not be used afterwards.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn kjv_search_free(results: *mut KjvSearchResults) {
+    if !results.is_null() {
+        drop(unsafe { Box::from_raw(results) });
+    }
+}
+
+/// # Safety
+/// `s` must be a string returned by this library (or NULL) and not be used afterwards.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn kjv_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(unsafe { CString::from_raw(s) });
+    }
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn kjv_status_message(status: i32) -> *const c_char {
+    let message: &'static CStr = match status {
+        KJV_OK => c"ok",
+        KJV_ERR_NULL_ARGUMENT => c"null argument",
+        KJV_ERR_INVALID_UTF8 => c"invalid UTF-8",
+        KJV_ERR_IO => c"I/O error",
+        KJV_ERR_DECODE => c"failed to decode bible",
+        KJV_ERR_INVALID_REFERENCE => c"invalid reference",
+        KJV_ERR_UNKNOWN_BOOK => c"unknown book",
+        KJV_ERR_CHAPTER_OUT_OF_RANGE => c"chapter out of range",
+        KJV_ERR_VERSE_OUT_OF_RANGE => c"verse out of range",
+        KJV_ERR_PANIC => c"internal error",
+        _ => c"unknown status",
+    };
+    message.as_ptr()
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn kjv_last_error() -> *const c_char {
+    LAST_ERROR.with(|e| e.borrow().as_ref().map_or(ptr::null(), |s| s.as_ptr()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parse_bible::{Book, Chapter, Verse, write_bible_to_bin};
+
+    // Tests run in parallel, so each writes its own file, named after the test
+    fn open_sample(name: &str) -> *mut KjvBible {
+        let bible = Bible {
+            ot_contents: vec!["Psalms".to_string()],
+            ot: vec![Book {
+                name: "Psalms".to_string(),
+                chapters: vec![Chapter {
+                    number: "23".to_string(),
+                    verses: vec![
+                        Verse {
+                            number: "1".to_string(),
+                            text: "The LORD is my shepherd; I shall not want.".to_string(),
+                        },
+                        Verse {
+                            number: "2".to_string(),
+                            text: "He maketh me to lie down in green pastures: he leadeth me beside the still waters.".to_string(),
+                        },
+                    ],
+                }],
+            }],
+            nt_contents: Vec::new(),
+            nt: Vec::new(),
+        };
+        let path =
+            std::env::temp_dir().join(format!("kjv-ffi-{}-{}.bin", name, std::process::id()));
+        write_bible_to_bin(&bible, path.to_str().unwrap()).unwrap();
+
+        let c_path = CString::new(path.to_str().unwrap()).unwrap();
+        let mut handle = ptr::null_mut();
+        let status = unsafe { kjv_open(c_path.as_ptr(), &mut handle) };
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(status, KJV_OK);
+        handle
+    }
+
+    fn take_string(s: *mut c_char) -> String {
+        let owned = unsafe { CStr::from_ptr(s) }.to_str().unwrap().to_string();
+        unsafe { kjv_string_free(s) };
+        owned
+    }
+
+    fn lookup(bible: *const KjvBible, reference: &str) -> Result<String, i32> {
+        let reference = CString::new(reference).unwrap();
+        let mut text = ptr::null_mut();
+        match unsafe { kjv_lookup(bible, reference.as_ptr(), &mut text) } {
+            KJV_OK => Ok(take_string(text)),
+            status => Err(status),
+        }
+    }
+
+    #[test]
+    fn looks_up_verses_and_chapters() {
+        let bible = open_sample("lookup");
+
+        assert_eq!(
+            lookup(bible, "Ps 23:1").as_deref(),
+            Ok("The LORD is my shepherd; I shall not want.")
+        );
+        let chapter = lookup(bible, "Psalm 23").unwrap();
+        assert_eq!(chapter.lines().count(), 2);
+        assert!(chapter.starts_with("1 The LORD is my shepherd"));
+
+        assert_eq!(
+            lookup(bible, "Psalms 24:1"),
+            Err(KJV_ERR_CHAPTER_OUT_OF_RANGE)
+        );
+        assert_eq!(
+            lookup(bible, "Psalms 23:9"),
+            Err(KJV_ERR_VERSE_OUT_OF_RANGE)
+        );
+        assert_eq!(lookup(bible, "Hezekiah 1:1"), Err(KJV_ERR_UNKNOWN_BOOK));
+        assert_eq!(lookup(bible, "Psalms"), Err(KJV_ERR_INVALID_REFERENCE));
+        let detail = unsafe { CStr::from_ptr(kjv_last_error()) };
+        assert_eq!(detail.to_str().unwrap(), "invalid reference 'Psalms'");
+
+        unsafe { kjv_close(bible) };
+    }
+
+    #[test]
+    fn iterates_search_results() {
+        let bible = open_sample("search");
+        let query = CString::new("still waters").unwrap();
+        let mut results = ptr::null_mut();
+        assert_eq!(
+            unsafe { kjv_search(bible, query.as_ptr(), &mut results) },
+            KJV_OK
+        );
+        unsafe { kjv_close(bible) };
+
+        assert_eq!(unsafe { kjv_search_count(results) }, 1);
+        let (mut reference, mut text) = (ptr::null_mut(), ptr::null_mut());
+        assert!(unsafe { kjv_search_next(results, &mut reference, &mut text) });
+        assert_eq!(take_string(reference), "Psalms 23:2");
+        assert!(take_string(text).ends_with("still waters."));
+        assert!(!unsafe { kjv_search_next(results, &mut reference, &mut text) });
+        assert!(!unsafe { kjv_search_next(ptr::null_mut(), &mut reference, &mut text) });
+        let detail = unsafe { CStr::from_ptr(kjv_last_error()) };
+        assert_eq!(detail.to_str().unwrap(), "results is NULL");
+        // Running out of hits after a failure is not an error
+        assert!(!unsafe { kjv_search_next(results, &mut reference, &mut text) });
+        assert!(kjv_last_error().is_null());
+
+        unsafe { kjv_search_free(results) };
+    }
+
+    #[test]
+    fn reports_open_and_argument_errors() {
+        let path = CString::new("/nonexistent/bible.bin").unwrap();
+        let mut handle = ptr::null_mut();
+        assert_eq!(unsafe { kjv_open(path.as_ptr(), &mut handle) }, KJV_ERR_IO);
+        assert!(handle.is_null());
+        assert_eq!(
+            unsafe { kjv_open(ptr::null(), &mut handle) },
+            KJV_ERR_NULL_ARGUMENT
+        );
+
+        let message = unsafe { CStr::from_ptr(kjv_status_message(KJV_ERR_DECODE)) };
+        assert_eq!(message.to_str().unwrap(), "failed to decode bible");
+    }
+}
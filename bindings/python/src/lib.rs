@@ -0,0 +1,429 @@
+// Python bindings, built into the `kjv` extension module with maturin
+use parse_bible::reference::resolve_book;
+use parse_bible::{Bible, Book, Chapter, ReferenceError, Verse, VerseRef, read_bible_from_bin};
+use pyo3::exceptions::{PyIOError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::sync::Arc;
+
+fn reference_error(e: ReferenceError) -> PyErr {
+    PyValueError::new_err(e.to_string())
+}
+
+#[pyclass(name = "Verse", module = "kjv", frozen, get_all)]
+#[derive(Clone)]
+struct PyVerse {
+    number: String,
+    text: String,
+}
+
+impl From<&Verse> for PyVerse {
+    fn from(v: &Verse) -> Self {
+        PyVerse {
+            number: v.number.clone(),
+            text: v.text.clone(),
+        }
+    }
+}
+
+#[pymethods]
+impl PyVerse {
+    fn __repr__(&self) -> String {
+        format!("Verse({}, {:?})", self.number, self.text)
+    }
+}
+
+// Books are numbered across both testaments, in the order of Bible::books
+fn book_at(bible: &Bible, index: usize) -> &Book {
+    match index.checked_sub(bible.ot.len()) {
+        Some(nt_index) => &bible.nt[nt_index],
+        None => &bible.ot[index],
+    }
+}
+
+// Chapters and books are views into the shared Bible rather than copies, so
+// walking `bible.books()` and each `book.chapters` does not clone any text
+#[pyclass(name = "Chapter", module = "kjv", frozen)]
+struct PyChapter {
+    bible: Arc<Bible>,
+    book: usize,
+    chapter: usize,
+}
+
+impl PyChapter {
+    fn inner(&self) -> &Chapter {
+        &book_at(&self.bible, self.book).chapters[self.chapter]
+    }
+}
+
+#[pymethods]
+impl PyChapter {
+    #[getter]
+    fn number(&self) -> &str {
+        &self.inner().number
+    }
+
+    #[getter]
+    fn verses(&self) -> Vec<PyVerse> {
+        self.inner().verses.iter().map(PyVerse::from).collect()
+    }
+
+    fn verse(&self, number: &str) -> Option<PyVerse> {
+        self.inner().verse(number).map(PyVerse::from)
+    }
+
+    fn __len__(&self) -> usize {
+        self.inner().verses.len()
+    }
+
+    fn __repr__(&self) -> String {
+        let chapter = self.inner();
+        format!(
+            "Chapter({}, {} verses)",
+            chapter.number,
+            chapter.verses.len()
+        )
+    }
+}
+
+#[pyclass(name = "Book", module = "kjv", frozen)]
+struct PyBook {
+    bible: Arc<Bible>,
+    book: usize,
+}
+
+impl PyBook {
+    fn inner(&self) -> &Book {
+        book_at(&self.bible, self.book)
+    }
+
+    fn chapter_at(&self, chapter: usize) -> PyChapter {
+        PyChapter {
+            bible: self.bible.clone(),
+            book: self.book,
+            chapter,
+        }
+    }
+}
+
+#[pymethods]
+impl PyBook {
+    #[getter]
+    fn name(&self) -> &str {
+        &self.inner().name
+    }
+
+    #[getter]
+    fn chapters(&self) -> Vec<PyChapter> {
+        (0..self.inner().chapters.len())
+            .map(|chapter| self.chapter_at(chapter))
+            .collect()
+    }
+
+    fn chapter(&self, number: &str) -> Option<PyChapter> {
+        self.inner()
+            .chapters
+            .iter()
+            .position(|c| c.number == number)
+            .map(|chapter| self.chapter_at(chapter))
+    }
+
+    fn __len__(&self) -> usize {
+        self.inner().chapters.len()
+    }
+
+    fn __repr__(&self) -> String {
+        let book = self.inner();
+        format!("Book({:?}, {} chapters)", book.name, book.chapters.len())
+    }
+}
+
+#[pyclass(name = "Reference", module = "kjv", frozen, get_all)]
+struct PyReference {
+    book: String,
+    chapter: u32,
+    verse: Option<u32>,
+    end_verse: Option<u32>,
+}
+
+#[pymethods]
+impl PyReference {
+    fn __str__(&self) -> String {
+        let reference = VerseRef {
+            book: resolve_book(&self.book).unwrap_or_default(),
+            chapter: self.chapter,
+            verse: self.verse,
+            end_verse: self.end_verse,
+        };
+        reference.to_string()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Reference({:?})", self.__str__())
+    }
+}
+
+#[pyclass(name = "Bible", module = "kjv", frozen)]
+struct PyBible {
+    inner: Arc<Bible>,
+}
+
+// One flat row per verse, the shape pandas.DataFrame expects
+fn record<'py>(
+    py: Python<'py>,
+    testament: &str,
+    book: &str,
+    chapter: &str,
+    verse: &Verse,
+) -> PyResult<Bound<'py, PyDict>> {
+    let row = PyDict::new(py);
+    row.set_item("testament", testament)?;
+    row.set_item("book", book)?;
+    row.set_item("chapter", chapter.parse::<u32>().unwrap_or_default())?;
+    row.set_item("verse", verse.number.parse::<u32>().unwrap_or_default())?;
+    row.set_item("text", &verse.text)?;
+    Ok(row)
+}
+
+impl PyBible {
+    fn new(bible: Bible) -> Self {
+        PyBible {
+            inner: Arc::new(bible),
+        }
+    }
+
+    fn books_in(&self, range: std::ops::Range<usize>) -> Vec<PyBook> {
+        range
+            .map(|book| PyBook {
+                bible: self.inner.clone(),
+                book,
+            })
+            .collect()
+    }
+
+    fn testament(&self, book: &str) -> &'static str {
+        if self.inner.ot.iter().any(|b| b.name == book) {
+            "OT"
+        } else {
+            "NT"
+        }
+    }
+}
+
+#[pymethods]
+impl PyBible {
+    #[getter]
+    fn ot_contents(&self) -> Vec<String> {
+        self.inner.ot_contents.clone()
+    }
+
+    #[getter]
+    fn nt_contents(&self) -> Vec<String> {
+        self.inner.nt_contents.clone()
+    }
+
+    #[getter]
+    fn ot(&self) -> Vec<PyBook> {
+        self.books_in(0..self.inner.ot.len())
+    }
+
+    #[getter]
+    fn nt(&self) -> Vec<PyBook> {
+        let ot = self.inner.ot.len();
+        self.books_in(ot..ot + self.inner.nt.len())
+    }
+
+    fn books(&self) -> Vec<PyBook> {
+        self.books_in(0..self.inner.books().count())
+    }
+
+    // Accepts abbreviations such as "Ps" or "1 Cor" as well as full names
+    fn book(&self, name: &str) -> Option<PyBook> {
+        let name = resolve_book(name).unwrap_or(name);
+        let book = self.inner.books().position(|b| b.name == name)?;
+        Some(PyBook {
+            bible: self.inner.clone(),
+            book,
+        })
+    }
+
+    fn lookup(&self, reference: &str) -> PyResult<Vec<PyVerse>> {
+        let reference = VerseRef::parse(reference).map_err(reference_error)?;
+        let verses = self.inner.lookup(&reference).map_err(reference_error)?;
+        Ok(verses.into_iter().map(PyVerse::from).collect())
+    }
+
+    fn search<'py>(&self, py: Python<'py>, query: &str) -> PyResult<Vec<Bound<'py, PyDict>>> {
+        self.inner
+            .search(query)
+            .into_iter()
+            .map(|hit| {
+                record(
+                    py,
+                    self.testament(hit.book),
+                    hit.book,
+                    hit.chapter,
+                    hit.verse,
+                )
+            })
+            .collect()
+    }
+
+    fn to_records<'py>(&self, py: Python<'py>) -> PyResult<Vec<Bound<'py, PyDict>>> {
+        let mut rows = Vec::new();
+        for (testament, books) in [("OT", &self.inner.ot), ("NT", &self.inner.nt)] {
+            for book in books {
+                for chapter in &book.chapters {
+                    for verse in &chapter.verses {
+                        rows.push(record(py, testament, &book.name, &chapter.number, verse)?);
+                    }
+                }
+            }
+        }
+        Ok(rows)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Bible({} OT books, {} NT books)",
+            self.inner.ot.len(),
+            self.inner.nt.len()
+        )
+    }
+}
+
+#[pyfunction]
+fn parse_gutenberg(text: &str) -> PyBible {
+    PyBible::new(parse_bible::parse_gutenberg(text))
+}
+
+#[pyfunction]
+fn load_bin(path: &str) -> PyResult<PyBible> {
+    match read_bible_from_bin(path) {
+        Ok(bible) => Ok(PyBible::new(bible)),
+        Err(e) if e.is::<std::io::Error>() => Err(PyIOError::new_err(e.to_string())),
+        Err(e) => Err(PyValueError::new_err(e.to_string())),
+    }
+}
+
+#[pyfunction]
+fn resolve_reference(reference: &str) -> PyResult<PyReference> {
+    let reference = VerseRef::parse(reference).map_err(reference_error)?;
+    Ok(PyReference {
+        book: reference.book.to_string(),
+        chapter: reference.chapter,
+        verse: reference.verse,
+        end_verse: reference.end_verse,
+    })
+}
+
+#[pymodule]
+fn kjv(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyBible>()?;
+    m.add_class::<PyBook>()?;
+    m.add_class::<PyChapter>()?;
+    m.add_class::<PyVerse>()?;
+    m.add_class::<PyReference>()?;
+    m.add_function(wrap_pyfunction!(parse_gutenberg, m)?)?;
+    m.add_function(wrap_pyfunction!(load_bin, m)?)?;
+    m.add_function(wrap_pyfunction!(resolve_reference, m)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parse_bible::write_bible_to_bin;
+    use pyo3::py_run;
+
+    fn sample_bin() -> String {
+        let verse = |number: &str, text: &str| Verse {
+            number: number.to_string(),
+            text: text.to_string(),
+        };
+        let bible = Bible {
+            ot_contents: vec!["Genesis".to_string()],
+            ot: vec![Book {
+                name: "Genesis".to_string(),
+                chapters: vec![Chapter {
+                    number: "1".to_string(),
+                    verses: vec![
+                        verse(
+                            "1",
+                            "In the beginning God created the heaven and the earth.",
+                        ),
+                        verse(
+                            "3",
+                            "And God said, Let there be light: and there was light.",
+                        ),
+                    ],
+                }],
+            }],
+            nt_contents: vec!["John".to_string()],
+            nt: vec![Book {
+                name: "John".to_string(),
+                chapters: vec![Chapter {
+                    number: "11".to_string(),
+                    verses: vec![verse("35", "Jesus wept.")],
+                }],
+            }],
+        };
+        let path = std::env::temp_dir().join(format!("kjv-python-{}.bin", std::process::id()));
+        let path = path.to_str().unwrap().to_string();
+        write_bible_to_bin(&bible, &path).unwrap();
+        path
+    }
+
+    #[test]
+    fn exposes_model_to_python() {
+        let path = sample_bin();
+        Python::with_gil(|py| {
+            let module = pyo3::wrap_pymodule!(kjv)(py);
+            py_run!(
+                py,
+                module path,
+                r#"
+bible = module.load_bin(path)
+assert repr(bible) == "Bible(1 OT books, 1 NT books)"
+assert bible.ot_contents == ["Genesis"]
+assert [b.name for b in bible.books()] == ["Genesis", "John"]
+
+genesis = bible.book("Gen")
+assert len(genesis) == 1
+assert repr(genesis.chapters[0]) == "Chapter(1, 2 verses)"
+assert repr(bible.nt[0]) == 'Book("John", 1 chapters)'
+assert bible.nt[0].chapter("11").verse("35").text == "Jesus wept."
+assert genesis.chapter("1").verse("3").text.startswith("And God said")
+assert bible.lookup("John 11:35")[0].text == "Jesus wept."
+
+ref = module.resolve_reference("Jn 11:35")
+assert (ref.book, ref.chapter, ref.verse, ref.end_verse) == ("John", 11, 35, None)
+assert str(ref) == "John 11:35"
+
+try:
+    bible.lookup("John 12:1")
+    raise AssertionError("expected ValueError")
+except ValueError as e:
+    assert "no chapter 12" in str(e)
+
+records = bible.to_records()
+assert len(records) == 3
+assert records[0] == {"testament": "OT", "book": "Genesis", "chapter": 1, "verse": 1,
+                      "text": "In the beginning God created the heaven and the earth."}
+assert records[2]["testament"] == "NT"
+assert [r["verse"] for r in bible.search("light")] == [3]
+"#
+            );
+        });
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn load_errors_become_python_exceptions() {
+        Python::with_gil(|py| {
+            let err = load_bin("/nonexistent/bible.bin").err().unwrap();
+            assert!(err.is_instance_of::<PyIOError>(py));
+            assert!(resolve_reference("Hezekiah 1:1").is_err());
+        });
+    }
+}
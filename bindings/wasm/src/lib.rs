@@ -0,0 +1,177 @@
+use parse_bible::{Bible, ReferenceError, VerseRef, decode_bible};
+use wasm_bindgen::prelude::*;
+
+// A resolved verse as handed to JavaScript
+#[wasm_bindgen(js_name = Verse, getter_with_clone)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct VerseEntry {
+    pub book: String,
+    pub chapter: String,
+    pub verse: String,
+    pub text: String,
+}
+
+// JavaScript facing wrapper around a Bible decoded from the bincode output of
+// write_bible_to_bin (uncompressed)
+#[wasm_bindgen(js_name = Bible)]
+pub struct WasmBible {
+    bible: Bible,
+}
+
+// Plain Rust API backing the bindings, so it can be tested natively without a
+// JavaScript runtime
+impl WasmBible {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let bible = decode_bible(bytes).map_err(|e| format!("failed to decode bible: {}", e))?;
+        Ok(Self { bible })
+    }
+
+    pub fn passage(&self, reference: &str) -> Result<Vec<VerseEntry>, ReferenceError> {
+        let reference = VerseRef::parse(reference)?;
+        let chapter = reference.chapter.to_string();
+        Ok(self
+            .bible
+            .lookup(&reference)?
+            .into_iter()
+            .map(|v| VerseEntry {
+                book: reference.book.to_string(),
+                chapter: chapter.clone(),
+                verse: v.number.clone(),
+                text: v.text.clone(),
+            })
+            .collect())
+    }
+
+    pub fn verse(&self, reference: &str) -> Result<VerseEntry, ReferenceError> {
+        let parsed = VerseRef::parse(reference)?;
+        if !parsed.is_single_verse() {
+            return Err(ReferenceError::InvalidReference(reference.to_string()));
+        }
+        Ok(self.passage(reference)?.remove(0))
+    }
+
+    pub fn chapter(&self, book: &str, chapter: u32) -> Result<Vec<VerseEntry>, ReferenceError> {
+        self.passage(&format!("{} {}", book, chapter))
+    }
+
+    pub fn find(&self, query: &str) -> Vec<VerseEntry> {
+        self.bible
+            .search(query)
+            .into_iter()
+            .map(|hit| VerseEntry {
+                book: hit.book.to_string(),
+                chapter: hit.chapter.to_string(),
+                verse: hit.verse.number.clone(),
+                text: hit.verse.text.clone(),
+            })
+            .collect()
+    }
+}
+
+fn js_error(e: impl std::fmt::Display) -> JsError {
+    JsError::new(&e.to_string())
+}
+
+#[wasm_bindgen(js_class = Bible)]
+impl WasmBible {
+    #[wasm_bindgen(constructor)]
+    pub fn new(bytes: &[u8]) -> Result<WasmBible, JsError> {
+        WasmBible::from_bytes(bytes).map_err(js_error)
+    }
+
+    // Canonical book names, Old Testament first
+    pub fn books(&self) -> Vec<String> {
+        self.bible.books().map(|b| b.name.clone()).collect()
+    }
+
+    #[wasm_bindgen(js_name = getVerse)]
+    pub fn get_verse(&self, reference: &str) -> Result<VerseEntry, JsError> {
+        self.verse(reference).map_err(js_error)
+    }
+
+    #[wasm_bindgen(js_name = getPassage)]
+    pub fn get_passage(&self, reference: &str) -> Result<Vec<VerseEntry>, JsError> {
+        self.passage(reference).map_err(js_error)
+    }
+
+    #[wasm_bindgen(js_name = getChapter)]
+    pub fn get_chapter(&self, book: &str, chapter: u32) -> Result<Vec<VerseEntry>, JsError> {
+        self.chapter(book, chapter).map_err(js_error)
+    }
+
+    pub fn search(&self, query: &str) -> Vec<VerseEntry> {
+        self.find(query)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parse_bible::{Book, Chapter, Verse};
+
+    fn encoded_bible() -> Vec<u8> {
+        let verses = [
+            "For God so loved the world, that he gave his only begotten Son, that whosoever believeth in him should not perish, but have everlasting life.",
+            "For God sent not his Son into the world to condemn the world; but that the world through him might be saved.",
+        ];
+        let bible = Bible {
+            ot_contents: Vec::new(),
+            ot: Vec::new(),
+            nt_contents: vec!["John".to_string()],
+            nt: vec![Book {
+                name: "John".to_string(),
+                chapters: vec![Chapter {
+                    number: "3".to_string(),
+                    verses: verses
+                        .iter()
+                        .enumerate()
+                        .map(|(i, text)| Verse {
+                            number: (i + 16).to_string(),
+                            text: text.to_string(),
+                        })
+                        .collect(),
+                }],
+            }],
+        };
+        bincode::encode_to_vec(&bible, bincode::config::standard()).unwrap()
+    }
+
+    #[test]
+    fn loads_bytes_and_looks_up_verses() {
+        let bible = WasmBible::from_bytes(&encoded_bible()).unwrap();
+        assert_eq!(bible.books(), vec!["John".to_string()]);
+
+        let verse = bible.verse("Jn 3:16").unwrap();
+        assert_eq!(
+            (
+                verse.book.as_str(),
+                verse.chapter.as_str(),
+                verse.verse.as_str()
+            ),
+            ("John", "3", "16")
+        );
+        assert!(verse.text.starts_with("For God so loved the world"));
+
+        assert_eq!(bible.passage("John 3:16-17").unwrap().len(), 2);
+        assert_eq!(bible.chapter("John", 3).unwrap().len(), 2);
+        assert!(bible.verse("John 3:16-17").is_err());
+        assert!(matches!(
+            bible.chapter("John", 4),
+            Err(ReferenceError::ChapterOutOfRange { .. })
+        ));
+    }
+
+    #[test]
+    fn searches_verse_text() {
+        let bible = WasmBible::from_bytes(&encoded_bible()).unwrap();
+        let hits = bible.find("world saved");
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].verse, "17");
+        assert!(bible.find("grace").is_empty());
+    }
+
+    #[test]
+    fn rejects_invalid_bytes() {
+        assert!(WasmBible::from_bytes(&[0xff, 0xff, 0xff]).is_err());
+    }
+}
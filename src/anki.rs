@@ -0,0 +1,516 @@
+use crate::model::Bible;
+use crate::reference::VerseRef;
+use serde_json::{Value, json};
+use std::error::Error;
+use std::fmt::Write as _;
+use std::io::{Cursor, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+use zip::CompressionMethod;
+use zip::write::{SimpleFileOptions, ZipWriter};
+
+// Fixed note type IDs, so decks exported again update the same note types
+// instead of adding copies
+const VERSE_MODEL_ID: i64 = 1_633_720_000_001;
+const CLOZE_MODEL_ID: i64 = 1_633_720_000_002;
+
+// Left out of cloze deletions: too common to be worth memorizing
+const STOP_WORDS: &[&str] = &[
+    "also", "been", "came", "come", "from", "have", "hath", "into", "made", "said", "shall",
+    "that", "thee", "them", "then", "there", "these", "they", "thine", "this", "thou", "thus",
+    "unto", "upon", "were", "what", "when", "which", "will", "with", "your",
+];
+
+#[derive(Clone, Debug)]
+pub struct DeckOptions {
+    pub name: String,
+    pub reference_to_text: bool,
+    pub text_to_reference: bool,
+    pub cloze: bool,
+    pub cloze_words: usize, // Key words blanked out per passage
+}
+
+impl Default for DeckOptions {
+    fn default() -> Self {
+        DeckOptions {
+            name: "KJV Memory Verses".to_string(),
+            reference_to_text: true,
+            text_to_reference: true,
+            cloze: true,
+            cloze_words: 3,
+        }
+    }
+}
+
+struct Passage {
+    reference: String,
+    text: String,
+    tag: String,
+}
+
+fn passages(bible: &Bible, references: &[VerseRef]) -> Result<Vec<Passage>, Box<dyn Error>> {
+    let mut passages = Vec::new();
+    for reference in references {
+        let verses = bible.lookup(reference)?;
+        let texts: Vec<&str> = verses.iter().map(|v| v.text.as_str()).collect();
+        passages.push(Passage {
+            reference: reference.to_string(),
+            text: texts.join(" "),
+            tag: reference.book.replace(' ', "_"),
+        });
+    }
+    Ok(passages)
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+// The text with its longest uncommon words turned into cloze deletions,
+// numbered in reading order so each becomes its own card
+pub fn cloze_text(text: &str, words: usize) -> String {
+    // Byte range of the letters in each word, leaving punctuation outside
+    let mut candidates = Vec::new();
+    let mut offset = 0;
+    for token in text.split(' ') {
+        let start = offset + token.len()
+            - token
+                .trim_start_matches(|c: char| !c.is_alphanumeric())
+                .len();
+        let end = offset + token.trim_end_matches(|c: char| !c.is_alphanumeric()).len();
+        offset += token.len() + 1;
+        if end <= start {
+            continue;
+        }
+        let word = text[start..end].to_lowercase();
+        if word.chars().count() >= 4 && !STOP_WORDS.contains(&word.as_str()) {
+            candidates.push((start, end, word));
+        }
+    }
+
+    let mut chosen: Vec<(usize, usize, String)> = Vec::new();
+    candidates.sort_by_key(|(start, end, _)| (std::cmp::Reverse(end - start), *start));
+    for candidate in candidates {
+        if chosen.len() == words {
+            break;
+        }
+        if !chosen.iter().any(|c| c.2 == candidate.2) {
+            chosen.push(candidate);
+        }
+    }
+    chosen.sort();
+
+    let mut out = String::new();
+    let mut copied = 0;
+    for (n, (start, end, _)) in chosen.iter().enumerate() {
+        out.push_str(&escape_html(&text[copied..*start]));
+        write!(
+            out,
+            "{{{{c{}::{}}}}}",
+            n + 1,
+            escape_html(&text[*start..*end])
+        )
+        .unwrap();
+        copied = *end;
+    }
+    out.push_str(&escape_html(&text[copied..]));
+    out
+}
+
+// Tab separated notes with the file headers understood by Anki 2.1.55 and
+// later (File > Import), using the built-in Basic and Cloze note types
+pub fn deck_tsv(
+    bible: &Bible,
+    references: &[VerseRef],
+    options: &DeckOptions,
+) -> Result<String, Box<dyn Error>> {
+    let mut out = format!(
+        "#separator:tab\n#html:true\n#notetype column:1\n#deck:{}\n#tags column:4\n",
+        options.name
+    );
+    for passage in passages(bible, references)? {
+        let reference = escape_html(&passage.reference);
+        let text = escape_html(&passage.text);
+        let basic = match (options.reference_to_text, options.text_to_reference) {
+            (true, true) => Some(("Basic (and reversed card)", &reference, &text)),
+            (true, false) => Some(("Basic", &reference, &text)),
+            (false, true) => Some(("Basic", &text, &reference)),
+            (false, false) => None,
+        };
+        if let Some((notetype, front, back)) = basic {
+            writeln!(out, "{}\t{}\t{}\t{}", notetype, front, back, passage.tag).unwrap();
+        }
+        // A cloze note without a deletion has no cards, and Anki rejects it
+        let cloze = cloze_text(&passage.text, options.cloze_words);
+        if options.cloze && cloze.contains("{{c") {
+            writeln!(out, "Cloze\t{}\t{}\t{}", cloze, reference, passage.tag).unwrap();
+        }
+    }
+    Ok(out)
+}
+
+fn field(name: &str, ord: usize) -> Value {
+    json!({"name": name, "ord": ord, "sticky": false, "rtl": false, "font": "Arial", "size": 20, "media": []})
+}
+
+fn template(name: &str, ord: usize, question: &str, answer: &str) -> Value {
+    json!({"name": name, "ord": ord, "qfmt": question, "afmt": answer, "did": null, "bqfmt": "", "bafmt": ""})
+}
+
+fn models(deck_id: i64, now: i64) -> Value {
+    let css = ".card { font-family: Georgia, serif; font-size: 22px; text-align: center; }";
+    let latex_pre = "\\documentclass[12pt]{article}\n\\special{papersize=3in,5in}\n\\usepackage{amssymb,amsmath}\n\\pagestyle{empty}\n\\begin{document}\n";
+    let verse = json!({
+        "id": VERSE_MODEL_ID, "name": "KJV Verse", "type": 0, "mod": now, "usn": -1, "sortf": 0,
+        "did": deck_id, "css": css, "latexPre": latex_pre, "latexPost": "\\end{document}",
+        "tags": [], "vers": [], "req": [[0, "any", [0]], [1, "any", [1]]],
+        "flds": [field("Reference", 0), field("Text", 1)],
+        "tmpls": [
+            template("Reference → Text", 0, "{{Reference}}", "{{FrontSide}}<hr id=answer>{{Text}}"),
+            template("Text → Reference", 1, "{{Text}}", "{{FrontSide}}<hr id=answer>{{Reference}}"),
+        ],
+    });
+    let mut cloze = verse.clone();
+    cloze["id"] = json!(CLOZE_MODEL_ID);
+    cloze["name"] = json!("KJV Cloze");
+    cloze["type"] = json!(1);
+    cloze["req"] = json!([[0, "any", [0]]]);
+    cloze["flds"] = json!([field("Text", 0), field("Reference", 1)]);
+    cloze["tmpls"] = json!([template(
+        "Cloze",
+        0,
+        "{{cloze:Text}}",
+        "{{cloze:Text}}<br>{{Reference}}"
+    )]);
+    json!({VERSE_MODEL_ID.to_string(): verse, CLOZE_MODEL_ID.to_string(): cloze})
+}
+
+fn deck(id: i64, name: &str, now: i64) -> Value {
+    json!({
+        "id": id, "name": name, "mod": now, "usn": -1, "collapsed": false, "desc": "",
+        "dyn": 0, "conf": 1, "extendNew": 10, "extendRev": 50,
+        "newToday": [0, 0], "revToday": [0, 0], "lrnToday": [0, 0], "timeToday": [0, 0],
+    })
+}
+
+const DECK_CONFIG: &str = r#"{"1": {"id": 1, "name": "Default", "mod": 0, "usn": 0, "maxTaken": 60,
+"autoplay": true, "timer": 0, "replayq": true,
+"new": {"bury": true, "delays": [1, 10], "initialFactor": 2500, "ints": [1, 4, 7], "order": 1, "perDay": 20, "separate": true},
+"lapse": {"delays": [10], "leechAction": 0, "leechFails": 8, "minInt": 1, "mult": 0},
+"rev": {"bury": true, "ease4": 1.3, "fuzz": 0.05, "ivlFct": 1, "maxIvl": 36500, "minSpace": 1, "perDay": 100}}}"#;
+
+const SCHEMA: &str = "
+CREATE TABLE col (id integer primary key, crt integer not null, mod integer not null,
+    scm integer not null, ver integer not null, dty integer not null, usn integer not null,
+    ls integer not null, conf text not null, models text not null, decks text not null,
+    dconf text not null, tags text not null);
+CREATE TABLE notes (id integer primary key, guid text not null, mid integer not null,
+    mod integer not null, usn integer not null, tags text not null, flds text not null,
+    sfld integer not null, csum integer not null, flags integer not null, data text not null);
+CREATE TABLE cards (id integer primary key, nid integer not null, did integer not null,
+    ord integer not null, mod integer not null, usn integer not null, type integer not null,
+    queue integer not null, due integer not null, ivl integer not null, factor integer not null,
+    reps integer not null, lapses integer not null, left integer not null, odue integer not null,
+    odid integer not null, flags integer not null, data text not null);
+CREATE TABLE revlog (id integer primary key, cid integer not null, usn integer not null,
+    ease integer not null, ivl integer not null, lastIvl integer not null,
+    factor integer not null, time integer not null, type integer not null);
+CREATE TABLE graves (usn integer not null, oid integer not null, type integer not null);
+CREATE INDEX ix_notes_usn on notes (usn);
+CREATE INDEX ix_cards_usn on cards (usn);
+CREATE INDEX ix_revlog_usn on revlog (usn);
+CREATE INDEX ix_cards_nid on cards (nid);
+CREATE INDEX ix_cards_sched on cards (did, queue, due);
+CREATE INDEX ix_revlog_cid on revlog (cid);
+CREATE INDEX ix_notes_csum on notes (csum);
+";
+
+// FNV-1a, for IDs and GUIDs that stay the same between exports
+fn stable_hash(s: &str) -> u64 {
+    s.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+// Anki finds duplicates through the first 32 bits of the SHA-1 of the sort field
+fn field_checksum(field: &str) -> i64 {
+    let digest = sha1_smol::Sha1::from(field).digest().bytes();
+    u32::from_be_bytes([digest[0], digest[1], digest[2], digest[3]]) as i64
+}
+
+// Rows of the notes and cards tables, with IDs counting up from the export
+// time like Anki's own
+struct Collection<'a> {
+    db: &'a rusqlite::Connection,
+    deck_id: i64,
+    secs: i64,
+    next_id: i64,
+    due: i64,
+}
+
+impl Collection<'_> {
+    fn add_note(
+        &mut self,
+        model: i64,
+        guid: &str,
+        tag: &str,
+        fields: [&str; 2],
+        ords: &[usize],
+    ) -> Result<(), Box<dyn Error>> {
+        self.next_id += 1;
+        let note_id = self.next_id;
+        self.db.execute(
+            "INSERT INTO notes VALUES (?1, ?2, ?3, ?4, -1, ?5, ?6, ?7, ?8, 0, '')",
+            rusqlite::params![
+                note_id,
+                guid,
+                model,
+                self.secs,
+                format!(" {} ", tag),
+                fields.join("\u{1f}"),
+                fields[0],
+                field_checksum(fields[0])
+            ],
+        )?;
+        for &ord in ords {
+            self.next_id += 1;
+            self.due += 1;
+            self.db.execute(
+                "INSERT INTO cards VALUES (?1, ?2, ?3, ?4, ?5, -1, 0, 0, ?6, 0, 0, 0, 0, 0, 0, 0, 0, '')",
+                rusqlite::params![self.next_id, note_id, self.deck_id, ord as i64, self.secs, self.due],
+            )?;
+        }
+        Ok(())
+    }
+}
+
+// Fills an empty Anki 2 collection: one note per passage and card type, and
+// the cards of the enabled directions and cloze deletions
+fn fill_collection(
+    db: &rusqlite::Connection,
+    passages: &[Passage],
+    options: &DeckOptions,
+) -> Result<(), Box<dyn Error>> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as i64;
+    let secs = now / 1000;
+    // Deck IDs are timestamps in Anki; keep them stable for the same name
+    let deck_id = 1_500_000_000_000 + (stable_hash(&options.name) % 100_000_000_000) as i64;
+    let decks = json!({"1": deck(1, "Default", secs), deck_id.to_string(): deck(deck_id, &options.name, secs)});
+    let conf = json!({
+        "activeDecks": [1], "curDeck": 1, "newSpread": 0, "collapseTime": 1200, "timeLim": 0,
+        "estTimes": true, "dueCounts": true, "curModel": null, "nextPos": 1,
+        "sortType": "noteFld", "sortBackwards": false, "addToCur": true,
+    });
+
+    db.execute_batch(SCHEMA)?;
+    db.execute(
+        "INSERT INTO col VALUES (1, ?1, ?2, ?2, 11, 0, 0, 0, ?3, ?4, ?5, ?6, '{}')",
+        rusqlite::params![
+            secs,
+            now,
+            conf.to_string(),
+            models(deck_id, secs).to_string(),
+            decks.to_string(),
+            DECK_CONFIG
+        ],
+    )?;
+
+    let mut collection = Collection {
+        db,
+        deck_id,
+        secs,
+        next_id: now,
+        due: 0,
+    };
+    // Same GUID for the same passage, so importing a deck again updates notes
+    let guid = |kind: &str, passage: &Passage| {
+        let key = format!("{}\u{1f}{}\u{1f}{}", options.name, kind, passage.reference);
+        format!("{:016x}", stable_hash(&key))
+    };
+    for passage in passages {
+        let reference = escape_html(&passage.reference);
+        let mut ords = Vec::new();
+        if options.reference_to_text {
+            ords.push(0);
+        }
+        if options.text_to_reference {
+            ords.push(1);
+        }
+        if !ords.is_empty() {
+            collection.add_note(
+                VERSE_MODEL_ID,
+                &guid("verse", passage),
+                &passage.tag,
+                [&reference, &escape_html(&passage.text)],
+                &ords,
+            )?;
+        }
+        if options.cloze {
+            let cloze = cloze_text(&passage.text, options.cloze_words);
+            let ords: Vec<usize> = (0..cloze.matches("{{c").count()).collect();
+            if !ords.is_empty() {
+                collection.add_note(
+                    CLOZE_MODEL_ID,
+                    &guid("cloze", passage),
+                    &passage.tag,
+                    [&cloze, &reference],
+                    &ords,
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// An .apkg package: a zip with the SQLite collection and an empty media map
+pub fn build_apkg(
+    bible: &Bible,
+    references: &[VerseRef],
+    options: &DeckOptions,
+) -> Result<Vec<u8>, Box<dyn Error>> {
+    let passages = passages(bible, references)?;
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH)?.subsec_nanos();
+    let path =
+        std::env::temp_dir().join(format!("kjv-anki-{}-{}.anki2", std::process::id(), nanos));
+    let result = (|| {
+        let db = rusqlite::Connection::open(&path)?;
+        fill_collection(&db, &passages, options)?;
+        db.close().map_err(|(_, e)| e)?;
+        Ok::<_, Box<dyn Error>>(std::fs::read(&path)?)
+    })();
+    let _ = std::fs::remove_file(&path);
+    let collection = result?;
+
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let deflated = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    zip.start_file("collection.anki2", deflated)?;
+    zip.write_all(&collection)?;
+    zip.start_file("media", deflated)?;
+    zip.write_all(b"{}")?;
+    Ok(zip.finish()?.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::sample_bible;
+    use std::io::Read;
+
+    fn references(refs: &[&str]) -> Vec<VerseRef> {
+        refs.iter().map(|r| VerseRef::parse(r).unwrap()).collect()
+    }
+
+    #[test]
+    fn blanks_out_key_words() {
+        assert_eq!(
+            cloze_text("The LORD is my shepherd; I shall not want.", 3),
+            "The {{c1::LORD}} is my {{c2::shepherd}}; I shall not {{c3::want}}."
+        );
+        assert_eq!(
+            cloze_text("In the beginning God created the heaven and the earth.", 2),
+            "In the {{c1::beginning}} God {{c2::created}} the heaven and the earth."
+        );
+        assert_eq!(cloze_text("I am.", 3), "I am.");
+    }
+
+    #[test]
+    fn writes_tsv_notes() {
+        let bible = sample_bible();
+        let refs = references(&["Ps 23:1", "Gen 1:1-2"]);
+        let tsv = deck_tsv(&bible, &refs, &DeckOptions::default()).unwrap();
+        let lines: Vec<&str> = tsv.lines().collect();
+        assert_eq!(lines[3], "#deck:KJV Memory Verses");
+        assert_eq!(
+            lines[5],
+            "Basic (and reversed card)\tPsalms 23:1\tThe LORD is my shepherd; I shall not want.\tPsalms"
+        );
+        assert_eq!(
+            lines[6],
+            "Cloze\tThe {{c1::LORD}} is my {{c2::shepherd}}; I shall not {{c3::want}}.\tPsalms 23:1\tPsalms"
+        );
+        assert!(lines[7].starts_with("Basic (and reversed card)\tGenesis 1:1-2\tIn the beginning God created the heaven and the earth. And the earth"));
+        assert_eq!(lines.len(), 9);
+
+        let options = DeckOptions {
+            reference_to_text: false,
+            cloze: false,
+            ..DeckOptions::default()
+        };
+        let tsv = deck_tsv(&bible, &refs[..1], &options).unwrap();
+        assert!(tsv.ends_with(
+            "\nBasic\tThe LORD is my shepherd; I shall not want.\tPsalms 23:1\tPsalms\n"
+        ));
+        assert!(deck_tsv(&bible, &references(&["John 3:5"]), &options).is_err());
+
+        // Nothing to blank out in a verse of short and common words
+        let mut bible = sample_bible();
+        bible.nt[0].chapters[0].verses[0].text = "And they said unto them, Come.".to_string();
+        let tsv = deck_tsv(&bible, &references(&["John 3:1"]), &DeckOptions::default()).unwrap();
+        assert_eq!(tsv.lines().count(), 6);
+        assert!(!tsv.contains("Cloze"));
+    }
+
+    #[test]
+    fn packages_an_anki_collection() {
+        let bible = sample_bible();
+        let refs = references(&["Ps 23:1", "John 3"]);
+        let data = build_apkg(&bible, &refs, &DeckOptions::default()).unwrap();
+
+        let mut archive = zip::ZipArchive::new(Cursor::new(data)).unwrap();
+        let mut media = String::new();
+        archive
+            .by_name("media")
+            .unwrap()
+            .read_to_string(&mut media)
+            .unwrap();
+        assert_eq!(media, "{}");
+        let mut collection = Vec::new();
+        archive
+            .by_name("collection.anki2")
+            .unwrap()
+            .read_to_end(&mut collection)
+            .unwrap();
+        let path = std::env::temp_dir().join(format!("kjv-anki-test-{}.anki2", std::process::id()));
+        std::fs::write(&path, collection).unwrap();
+
+        let db = rusqlite::Connection::open(&path).unwrap();
+        let count = |sql: &str| db.query_row(sql, [], |row| row.get::<_, i64>(0)).unwrap();
+        assert_eq!(count("SELECT count(*) FROM notes"), 4);
+        // Two directions and three clozes per passage
+        assert_eq!(count("SELECT count(*) FROM cards"), 10);
+        assert_eq!(
+            count("SELECT count(*) FROM cards WHERE nid NOT IN (SELECT id FROM notes)"),
+            0
+        );
+
+        let models: String = db
+            .query_row("SELECT models FROM col", [], |row| row.get(0))
+            .unwrap();
+        let models: Value = serde_json::from_str(&models).unwrap();
+        assert_eq!(
+            models[VERSE_MODEL_ID.to_string()]["tmpls"]
+                .as_array()
+                .unwrap()
+                .len(),
+            2
+        );
+        assert_eq!(models[CLOZE_MODEL_ID.to_string()]["type"], 1);
+
+        let (flds, csum): (String, i64) = db
+            .query_row(
+                "SELECT flds, csum FROM notes WHERE mid = ?1 ORDER BY id",
+                [VERSE_MODEL_ID],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(
+            flds,
+            "Psalms 23:1\u{1f}The LORD is my shepherd; I shall not want."
+        );
+        assert_eq!(csum, field_checksum("Psalms 23:1"));
+
+        drop(db);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,505 @@
+use crate::model::Bible;
+use memmap2::Mmap;
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+// Zero-copy archive layout. Every integer is a little-endian u32 and every
+// string is an (offset, length) pair into the string section, so readers can
+// hand out &str slices straight from the (memory-mapped) file.
+//
+//   header    "KJVA", version, counts and section offsets (see Layout)
+//   books     name, first chapter index, chapter count
+//   chapters  number, first verse index, verse count
+//   verses    number, text
+//   contents  OT table of contents followed by the NT one
+//   strings   UTF-8 bytes of every name, number and verse text
+const MAGIC: &[u8; 4] = b"KJVA";
+const VERSION: u32 = 1;
+const HEADER_FIELDS: usize = 13;
+const HEADER_LEN: usize = MAGIC.len() + HEADER_FIELDS * 4;
+const RECORD_LEN: usize = 16;
+const STR_REF_LEN: usize = 8;
+
+fn read_u32(data: &[u8], pos: usize) -> usize {
+    u32::from_le_bytes(data[pos..pos + 4].try_into().unwrap()) as usize
+}
+
+#[derive(Default)]
+struct StringTable {
+    bytes: Vec<u8>,
+}
+
+impl StringTable {
+    fn add(&mut self, s: &str) -> [u32; 2] {
+        let offset = self.bytes.len() as u32;
+        self.bytes.extend_from_slice(s.as_bytes());
+        [offset, s.len() as u32]
+    }
+}
+
+pub fn encode_archive(bible: &Bible) -> Vec<u8> {
+    let mut strings = StringTable::default();
+    let mut books: Vec<u32> = Vec::new();
+    let mut chapters: Vec<u32> = Vec::new();
+    let mut verses: Vec<u32> = Vec::new();
+    let mut contents: Vec<u32> = Vec::new();
+
+    let mut chapter_count = 0u32;
+    let mut verse_count = 0u32;
+    for book in bible.books() {
+        books.extend(strings.add(&book.name));
+        books.extend([chapter_count, book.chapters.len() as u32]);
+        chapter_count += book.chapters.len() as u32;
+
+        for chapter in &book.chapters {
+            chapters.extend(strings.add(&chapter.number));
+            chapters.extend([verse_count, chapter.verses.len() as u32]);
+            verse_count += chapter.verses.len() as u32;
+
+            for verse in &chapter.verses {
+                verses.extend(strings.add(&verse.number));
+                verses.extend(strings.add(&verse.text));
+            }
+        }
+    }
+
+    for name in bible.ot_contents.iter().chain(&bible.nt_contents) {
+        contents.extend(strings.add(name));
+    }
+
+    let books_off = HEADER_LEN;
+    let chapters_off = books_off + books.len() * 4;
+    let verses_off = chapters_off + chapters.len() * 4;
+    let contents_off = verses_off + verses.len() * 4;
+    let strings_off = contents_off + contents.len() * 4;
+
+    let header = [
+        VERSION,
+        bible.ot.len() as u32,
+        (bible.ot.len() + bible.nt.len()) as u32,
+        chapter_count,
+        verse_count,
+        bible.ot_contents.len() as u32,
+        bible.nt_contents.len() as u32,
+        books_off as u32,
+        chapters_off as u32,
+        verses_off as u32,
+        contents_off as u32,
+        strings_off as u32,
+        strings.bytes.len() as u32,
+    ];
+
+    let mut out = Vec::with_capacity(strings_off + strings.bytes.len());
+    out.extend_from_slice(MAGIC);
+    for value in header.iter().chain(&books).chain(&chapters).chain(&verses) {
+        out.extend_from_slice(&value.to_le_bytes());
+    }
+    for value in &contents {
+        out.extend_from_slice(&value.to_le_bytes());
+    }
+    out.extend_from_slice(&strings.bytes);
+    out
+}
+
+pub fn write_bible_to_archive(bible: &Bible, path: &str) -> Result<(), Box<dyn Error>> {
+    let f = File::create(path)?;
+    let mut writer = BufWriter::new(f);
+    writer.write_all(&encode_archive(bible))?;
+    writer.flush()?;
+    Ok(())
+}
+
+#[derive(Clone, Copy)]
+struct Layout {
+    ot_books: usize,
+    books: usize,
+    chapters: usize,
+    verses: usize,
+    ot_contents: usize,
+    nt_contents: usize,
+    books_off: usize,
+    chapters_off: usize,
+    verses_off: usize,
+    contents_off: usize,
+    strings_off: usize,
+    strings_len: usize,
+}
+
+impl Layout {
+    // Checks the header and every record once, so that the accessors can
+    // index into the data without returning errors afterwards
+    fn validate(data: &[u8]) -> Result<Self, Box<dyn Error>> {
+        if data.len() < HEADER_LEN || &data[..MAGIC.len()] != MAGIC {
+            return Err("not a bible archive".into());
+        }
+
+        let field = |i: usize| read_u32(data, MAGIC.len() + i * 4);
+        if field(0) != VERSION as usize {
+            return Err(format!("unsupported archive version {}", field(0)).into());
+        }
+
+        let layout = Layout {
+            ot_books: field(1),
+            books: field(2),
+            chapters: field(3),
+            verses: field(4),
+            ot_contents: field(5),
+            nt_contents: field(6),
+            books_off: field(7),
+            chapters_off: field(8),
+            verses_off: field(9),
+            contents_off: field(10),
+            strings_off: field(11),
+            strings_len: field(12),
+        };
+
+        let sections = [
+            (layout.books_off, layout.books.checked_mul(RECORD_LEN)),
+            (layout.chapters_off, layout.chapters.checked_mul(RECORD_LEN)),
+            (layout.verses_off, layout.verses.checked_mul(RECORD_LEN)),
+            (
+                layout.contents_off,
+                (layout.ot_contents + layout.nt_contents).checked_mul(STR_REF_LEN),
+            ),
+            (layout.strings_off, Some(layout.strings_len)),
+        ];
+        for (offset, len) in sections {
+            let end = len.and_then(|len| offset.checked_add(len));
+            if end.is_none_or(|end| end > data.len()) {
+                return Err("archive section out of bounds".into());
+            }
+        }
+
+        if layout.ot_books > layout.books {
+            return Err("archive has more OT books than books".into());
+        }
+
+        for i in 0..layout.books {
+            let pos = layout.books_off + i * RECORD_LEN;
+            layout.check_str(data, pos)?;
+            layout.check_range(data, pos + STR_REF_LEN, layout.chapters)?;
+        }
+        for i in 0..layout.chapters {
+            let pos = layout.chapters_off + i * RECORD_LEN;
+            layout.check_str(data, pos)?;
+            layout.check_range(data, pos + STR_REF_LEN, layout.verses)?;
+        }
+        for i in 0..layout.verses {
+            let pos = layout.verses_off + i * RECORD_LEN;
+            layout.check_str(data, pos)?;
+            layout.check_str(data, pos + STR_REF_LEN)?;
+        }
+        for i in 0..layout.ot_contents + layout.nt_contents {
+            layout.check_str(data, layout.contents_off + i * STR_REF_LEN)?;
+        }
+
+        Ok(layout)
+    }
+
+    fn str_at<'a>(&self, data: &'a [u8], pos: usize) -> Option<&'a str> {
+        let offset = read_u32(data, pos);
+        let len = read_u32(data, pos + 4);
+        let end = offset.checked_add(len)?;
+        if end > self.strings_len {
+            return None;
+        }
+        std::str::from_utf8(&data[self.strings_off + offset..self.strings_off + end]).ok()
+    }
+
+    fn check_str(&self, data: &[u8], pos: usize) -> Result<(), Box<dyn Error>> {
+        match self.str_at(data, pos) {
+            Some(_) => Ok(()),
+            None => Err(format!("invalid string reference at byte {}", pos).into()),
+        }
+    }
+
+    fn check_range(&self, data: &[u8], pos: usize, total: usize) -> Result<(), Box<dyn Error>> {
+        let first = read_u32(data, pos);
+        let count = read_u32(data, pos + 4);
+        if first.checked_add(count).is_none_or(|end| end > total) {
+            return Err(format!("invalid index range at byte {}", pos).into());
+        }
+        Ok(())
+    }
+}
+
+// Read-only view of an archive that borrows all text from the underlying bytes
+#[derive(Clone, Copy)]
+pub struct ArchivedBible<'a> {
+    data: &'a [u8],
+    layout: Layout,
+}
+
+#[derive(Clone, Copy)]
+pub struct ArchivedBook<'a> {
+    bible: ArchivedBible<'a>,
+    pos: usize,
+}
+
+#[derive(Clone, Copy)]
+pub struct ArchivedChapter<'a> {
+    bible: ArchivedBible<'a>,
+    pos: usize,
+}
+
+#[derive(Clone, Copy)]
+pub struct ArchivedVerse<'a> {
+    bible: ArchivedBible<'a>,
+    pos: usize,
+}
+
+impl<'a> ArchivedBible<'a> {
+    pub fn from_bytes(data: &'a [u8]) -> Result<Self, Box<dyn Error>> {
+        let layout = Layout::validate(data)?;
+        Ok(Self { data, layout })
+    }
+
+    fn str_at(self, pos: usize) -> &'a str {
+        let start = self.layout.strings_off + read_u32(self.data, pos);
+        let len = read_u32(self.data, pos + 4);
+        let bytes = &self.data[start..start + len];
+        // SAFETY: an ArchivedBible only exists for data that passed
+        // Layout::validate, which checked that every string reference in it
+        // is UTF-8, and `pos` is always one of those references
+        unsafe { std::str::from_utf8_unchecked(bytes) }
+    }
+
+    fn contents(self, range: std::ops::Range<usize>) -> impl Iterator<Item = &'a str> + 'a {
+        range.map(move |i| self.str_at(self.layout.contents_off + i * STR_REF_LEN))
+    }
+
+    fn book_range(
+        self,
+        range: std::ops::Range<usize>,
+    ) -> impl Iterator<Item = ArchivedBook<'a>> + 'a {
+        range.map(move |i| ArchivedBook {
+            bible: self,
+            pos: self.layout.books_off + i * RECORD_LEN,
+        })
+    }
+
+    pub fn ot_contents(self) -> impl Iterator<Item = &'a str> + 'a {
+        self.contents(0..self.layout.ot_contents)
+    }
+
+    pub fn nt_contents(self) -> impl Iterator<Item = &'a str> + 'a {
+        let ot = self.layout.ot_contents;
+        self.contents(ot..ot + self.layout.nt_contents)
+    }
+
+    pub fn ot(self) -> impl Iterator<Item = ArchivedBook<'a>> + 'a {
+        self.book_range(0..self.layout.ot_books)
+    }
+
+    pub fn nt(self) -> impl Iterator<Item = ArchivedBook<'a>> + 'a {
+        self.book_range(self.layout.ot_books..self.layout.books)
+    }
+
+    // All books in canonical order, Old Testament first
+    pub fn books(self) -> impl Iterator<Item = ArchivedBook<'a>> + 'a {
+        self.book_range(0..self.layout.books)
+    }
+
+    pub fn book(self, name: &str) -> Option<ArchivedBook<'a>> {
+        self.books().find(|b| b.name() == name)
+    }
+
+    pub fn chapter(self, book: &str, chapter: &str) -> Option<ArchivedChapter<'a>> {
+        self.book(book)?.chapter(chapter)
+    }
+
+    pub fn verse(self, book: &str, chapter: &str, verse: &str) -> Option<ArchivedVerse<'a>> {
+        self.chapter(book, chapter)?.verse(verse)
+    }
+}
+
+impl<'a> ArchivedBook<'a> {
+    pub fn name(&self) -> &'a str {
+        self.bible.str_at(self.pos)
+    }
+
+    pub fn chapters(&self) -> impl Iterator<Item = ArchivedChapter<'a>> + 'a {
+        let bible = self.bible;
+        let first = read_u32(bible.data, self.pos + STR_REF_LEN);
+        let count = read_u32(bible.data, self.pos + STR_REF_LEN + 4);
+        (first..first + count).map(move |i| ArchivedChapter {
+            bible,
+            pos: bible.layout.chapters_off + i * RECORD_LEN,
+        })
+    }
+
+    pub fn chapter(&self, number: &str) -> Option<ArchivedChapter<'a>> {
+        self.chapters().find(|c| c.number() == number)
+    }
+}
+
+impl<'a> ArchivedChapter<'a> {
+    pub fn number(&self) -> &'a str {
+        self.bible.str_at(self.pos)
+    }
+
+    pub fn verses(&self) -> impl Iterator<Item = ArchivedVerse<'a>> + 'a {
+        let bible = self.bible;
+        let first = read_u32(bible.data, self.pos + STR_REF_LEN);
+        let count = read_u32(bible.data, self.pos + STR_REF_LEN + 4);
+        (first..first + count).map(move |i| ArchivedVerse {
+            bible,
+            pos: bible.layout.verses_off + i * RECORD_LEN,
+        })
+    }
+
+    pub fn verse(&self, number: &str) -> Option<ArchivedVerse<'a>> {
+        self.verses().find(|v| v.number() == number)
+    }
+}
+
+impl<'a> ArchivedVerse<'a> {
+    pub fn number(&self) -> &'a str {
+        self.bible.str_at(self.pos)
+    }
+
+    pub fn text(&self) -> &'a str {
+        self.bible.str_at(self.pos + STR_REF_LEN)
+    }
+}
+
+// Archive backed by a read-only memory map. Opening it checks every record
+// and that each string is UTF-8, one pass over the file; after that lookups
+// borrow from the map without decoding verses into owned Strings
+pub struct MappedBible {
+    mmap: Mmap,
+    layout: Layout,
+}
+
+impl MappedBible {
+    pub fn open(path: &str) -> Result<Self, Box<dyn Error>> {
+        let file = File::open(path)?;
+        // SAFETY: the mapping is only ever read; modifying or truncating the
+        // file while it is mapped is not supported
+        let mmap = unsafe { Mmap::map(&file)? };
+        let layout = Layout::validate(&mmap)?;
+        Ok(Self { mmap, layout })
+    }
+
+    pub fn archive(&self) -> ArchivedBible<'_> {
+        ArchivedBible {
+            data: &self.mmap,
+            layout: self.layout,
+        }
+    }
+
+    pub fn ot_contents(&self) -> impl Iterator<Item = &str> + '_ {
+        self.archive().ot_contents()
+    }
+
+    pub fn nt_contents(&self) -> impl Iterator<Item = &str> + '_ {
+        self.archive().nt_contents()
+    }
+
+    pub fn ot(&self) -> impl Iterator<Item = ArchivedBook<'_>> + '_ {
+        self.archive().ot()
+    }
+
+    pub fn nt(&self) -> impl Iterator<Item = ArchivedBook<'_>> + '_ {
+        self.archive().nt()
+    }
+
+    pub fn books(&self) -> impl Iterator<Item = ArchivedBook<'_>> + '_ {
+        self.archive().books()
+    }
+
+    pub fn book(&self, name: &str) -> Option<ArchivedBook<'_>> {
+        self.archive().book(name)
+    }
+
+    pub fn chapter(&self, book: &str, chapter: &str) -> Option<ArchivedChapter<'_>> {
+        self.archive().chapter(book, chapter)
+    }
+
+    pub fn verse(&self, book: &str, chapter: &str, verse: &str) -> Option<ArchivedVerse<'_>> {
+        self.archive().verse(book, chapter, verse)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::sample_bible;
+
+    #[test]
+    fn archive_matches_owned_bible() {
+        let bible = sample_bible();
+        let bytes = encode_archive(&bible);
+        let archive = ArchivedBible::from_bytes(&bytes).expect("valid archive");
+
+        assert!(
+            archive
+                .ot_contents()
+                .eq(bible.ot_contents.iter().map(String::as_str))
+        );
+        assert!(
+            archive
+                .nt_contents()
+                .eq(bible.nt_contents.iter().map(String::as_str))
+        );
+        assert_eq!(archive.ot().count(), bible.ot.len());
+        assert_eq!(archive.nt().count(), bible.nt.len());
+
+        for (book, archived_book) in bible.books().zip(archive.books()) {
+            assert_eq!(archived_book.name(), book.name);
+            assert_eq!(archived_book.chapters().count(), book.chapters.len());
+            for (chapter, archived_chapter) in book.chapters.iter().zip(archived_book.chapters()) {
+                assert_eq!(archived_chapter.number(), chapter.number);
+                assert_eq!(archived_chapter.verses().count(), chapter.verses.len());
+                for (verse, archived_verse) in chapter.verses.iter().zip(archived_chapter.verses())
+                {
+                    assert_eq!(archived_verse.number(), verse.number);
+                    assert_eq!(archived_verse.text(), verse.text);
+                }
+            }
+        }
+
+        assert_eq!(
+            archive.verse("Psalms", "23", "1").map(|v| v.text()),
+            Some("The LORD is my shepherd; I shall not want.")
+        );
+        assert!(archive.verse("Psalms", "24", "1").is_none());
+    }
+
+    #[test]
+    fn mapped_bible_reads_written_archive() {
+        let path = std::env::temp_dir().join(format!("kjv-archive-{}.kjva", std::process::id()));
+        let path = path.to_str().unwrap();
+        write_bible_to_archive(&sample_bible(), path).expect("Failed to write archive");
+
+        let mapped = MappedBible::open(path).expect("Failed to map archive");
+        assert_eq!(mapped.books().count(), 3);
+        assert!(mapped.ot().map(|b| b.name()).eq(["Genesis", "Psalms"]));
+        assert!(mapped.nt().map(|b| b.name()).eq(["John"]));
+        assert_eq!(
+            mapped.verse("Genesis", "1", "3").map(|v| v.text()),
+            Some("And God said, Let there be light: and there was light.")
+        );
+        drop(mapped);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn rejects_corrupt_archives() {
+        let bytes = encode_archive(&sample_bible());
+
+        assert!(ArchivedBible::from_bytes(b"not an archive").is_err());
+        assert!(ArchivedBible::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+
+        let mut bad_version = bytes.clone();
+        bad_version[4] = 99;
+        assert!(ArchivedBible::from_bytes(&bad_version).is_err());
+
+        // Accessors rely on every string having been checked as UTF-8
+        let mut bad_text = bytes.clone();
+        let at = bytes.windows(9).position(|w| w == b"beginning").unwrap();
+        bad_text[at] = 0xff;
+        assert!(ArchivedBible::from_bytes(&bad_text).is_err());
+    }
+}
@@ -0,0 +1,18 @@
+// mdBook preprocessor expanding {{kjv John 3:16-18}} placeholders. Enable it
+// in book.toml with:
+//
+//     [preprocessor.kjv]
+//     bible = "bible.bin"   # relative to the book root
+//     open = "{{kjv"        # optional, placeholder delimiters
+//     close = "}}"
+use parse_bible::mdbook::run_preprocessor;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        // Placeholders are plain Markdown, so every renderer is supported
+        Some("supports") => Ok(()),
+        Some(arg) => Err(format!("unknown argument '{}'", arg).into()),
+        None => run_preprocessor(std::io::stdin().lock(), std::io::stdout().lock()),
+    }
+}
@@ -0,0 +1,197 @@
+use crate::model::{Bible, Book};
+use std::error::Error;
+use std::fmt::Write as _;
+
+// Item definitions shared by every generated module. Only `core` is used so the
+// output works in no_std and WASM crates.
+const PRELUDE: &str = r#"#[derive(Debug)]
+pub struct Verse {
+    pub number: &'static str,
+    pub text: &'static str,
+}
+
+#[derive(Debug)]
+pub struct Chapter {
+    pub number: &'static str,
+    pub verses: &'static [Verse],
+}
+
+#[derive(Debug)]
+pub struct Book {
+    pub name: &'static str,
+    pub chapters: &'static [Chapter],
+}
+
+impl Book {
+    pub fn chapter(&self, number: &str) -> Option<&'static Chapter> {
+        self.chapters.iter().find(|c| c.number == number)
+    }
+}
+
+impl Chapter {
+    pub fn verse(&self, number: &str) -> Option<&'static Verse> {
+        self.verses.iter().find(|v| v.number == number)
+    }
+}
+
+// All books in canonical order, Old Testament first
+pub fn books() -> impl Iterator<Item = &'static Book> {
+    OT.iter().chain(NT.iter())
+}
+
+pub fn book(name: &str) -> Option<&'static Book> {
+    books().find(|b| b.name == name)
+}
+
+pub fn verse(book_name: &str, chapter: &str, verse: &str) -> Option<&'static Verse> {
+    book(book_name)?.chapter(chapter)?.verse(verse)
+}
+"#;
+
+// Emits a self-contained Rust module with the whole Bible as static data.
+// Every chapter gets its own verse array so rustc never has to deal with one
+// enormous nested constant.
+pub fn generate_static_module(bible: &Bible) -> String {
+    let mut out = String::new();
+    out.push_str("// @generated by parse-bible from the Project Gutenberg KJV. Do not edit.\n\n");
+    out.push_str(PRELUDE);
+
+    write_contents(&mut out, "OT_CONTENTS", &bible.ot_contents);
+    write_contents(&mut out, "NT_CONTENTS", &bible.nt_contents);
+    write_testament(&mut out, "OT", &bible.ot);
+    write_testament(&mut out, "NT", &bible.nt);
+
+    out
+}
+
+fn write_contents(out: &mut String, name: &str, contents: &[String]) {
+    writeln!(out, "\npub static {}: &[&str] = &[", name).unwrap();
+    for entry in contents {
+        writeln!(out, "    {:?},", entry).unwrap();
+    }
+    out.push_str("];\n");
+}
+
+fn write_testament(out: &mut String, name: &str, books: &[Book]) {
+    for (b, book) in books.iter().enumerate() {
+        for (c, chapter) in book.chapters.iter().enumerate() {
+            writeln!(
+                out,
+                "\nstatic {}_{}_{}: [Verse; {}] = [",
+                name,
+                b,
+                c,
+                chapter.verses.len()
+            )
+            .unwrap();
+            for verse in &chapter.verses {
+                writeln!(
+                    out,
+                    "    Verse {{ number: {:?}, text: {:?} }},",
+                    verse.number, verse.text
+                )
+                .unwrap();
+            }
+            out.push_str("];\n");
+        }
+    }
+
+    writeln!(out, "\npub static {}: &[Book] = &[", name).unwrap();
+    for (b, book) in books.iter().enumerate() {
+        writeln!(
+            out,
+            "    Book {{\n        name: {:?},\n        chapters: &[",
+            book.name
+        )
+        .unwrap();
+        for (c, chapter) in book.chapters.iter().enumerate() {
+            writeln!(
+                out,
+                "            Chapter {{ number: {:?}, verses: &{}_{}_{} }},",
+                chapter.number, name, b, c
+            )
+            .unwrap();
+        }
+        out.push_str("        ],\n    },\n");
+    }
+    out.push_str("];\n");
+}
+
+pub fn write_static_module(bible: &Bible, path: &str) -> Result<(), Box<dyn Error>> {
+    std::fs::write(path, generate_static_module(bible))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::sample_bible;
+
+    #[test]
+    fn emits_statics_for_every_chapter() {
+        let bible = sample_bible();
+        let module = generate_static_module(&bible);
+
+        assert!(module.starts_with("// @generated"));
+        assert!(module.contains(
+            "pub static OT_CONTENTS: &[&str] = &[\n    \"Genesis\",\n    \"Psalms\",\n];"
+        ));
+        assert!(module.contains("static OT_0_0: [Verse; 3] = ["));
+        assert!(module.contains("static OT_1_0: [Verse; 2] = ["));
+        assert!(module.contains("static NT_0_0: [Verse; 2] = ["));
+        assert!(module.contains("Chapter { number: \"23\", verses: &OT_1_0 },"));
+        assert!(module.contains(
+            "Verse { number: \"1\", text: \"The LORD is my shepherd; I shall not want.\" },"
+        ));
+
+        let verse_count: usize = bible
+            .books()
+            .flat_map(|b| &b.chapters)
+            .map(|c| c.verses.len())
+            .sum();
+        assert_eq!(module.matches("    Verse { number: ").count(), verse_count);
+    }
+
+    // Type-checks the output with the rustc running the tests, so a syntax or
+    // type error in the generated code fails here and not in someone's crate
+    #[test]
+    fn generated_module_compiles() {
+        let mut bible = sample_bible();
+        bible.ot[0].chapters[0].verses[0].text = "Quotes \" and \\ escapes".to_string();
+        let dir = std::env::temp_dir().join(format!("kjv-codegen-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("kjv.rs");
+        write_static_module(&bible, path.to_str().unwrap()).unwrap();
+
+        let rustc = std::env::var_os("RUSTC").unwrap_or("rustc".into());
+        let output = std::process::Command::new(rustc)
+            .args([
+                "--edition",
+                "2024",
+                "--crate-type",
+                "lib",
+                "--emit",
+                "metadata",
+            ])
+            .args(["-D", "warnings", "--out-dir"])
+            .arg(&dir)
+            .arg(&path)
+            .output()
+            .unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    #[test]
+    fn escapes_string_literals() {
+        let mut bible = sample_bible();
+        bible.ot[0].chapters[0].verses[0].text = "He said, \"Let there be light\"\\".to_string();
+
+        let module = generate_static_module(&bible);
+        assert!(module.contains(r#"text: "He said, \"Let there be light\"\\" }"#));
+    }
+}
@@ -0,0 +1,307 @@
+use crate::model::Bible;
+use flate2::Compression as GzLevel;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use std::error::Error;
+use std::io::{Read, Write};
+use std::str::FromStr;
+use zstd::stream::raw::CParameter;
+use zstd::zstd_safe::DictAttachPref;
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+// zstd's own default dictionary size
+const ZSTD_DICT_SIZE: usize = 110 * 1024;
+const ZSTD_DEFAULT_LEVEL: i32 = 19;
+
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub enum Compression {
+    #[default]
+    None,
+    Gzip,
+    Zstd,
+    // zstd against a dictionary from train_dictionary. The frame only records
+    // the dictionary id: the dictionary is shipped to clients once, separately,
+    // instead of being paid for in every file.
+    ZstdDict,
+}
+
+impl Compression {
+    // Suffix appended to output file names
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Compression::None => "",
+            Compression::Gzip => ".gz",
+            Compression::Zstd | Compression::ZstdDict => ".zst",
+        }
+    }
+}
+
+impl FromStr for Compression {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Compression::None),
+            "gzip" | "gz" => Ok(Compression::Gzip),
+            "zstd" | "zst" => Ok(Compression::Zstd),
+            "zstd-dict" => Ok(Compression::ZstdDict),
+            _ => Err(format!(
+                "unknown compression '{}' (expected none, gzip, zstd or zstd-dict)",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Default, Debug)]
+pub struct WriteOptions {
+    pub compression: Compression,
+    // Codec specific level, the codec default is used when unset
+    pub level: Option<i32>,
+    // Required by ZstdDict and ignored by the other codecs
+    pub dictionary: Option<Vec<u8>>,
+}
+
+impl WriteOptions {
+    // Rejects levels the codec does not have: 0-9 for gzip, zstd's own range
+    // (negative levels are its fast modes) and any level without compression
+    pub fn check_level(&self) -> Result<(), String> {
+        let Some(level) = self.level else {
+            return Ok(());
+        };
+        let range = match self.compression {
+            Compression::None => return Err(format!("level {} given without compression", level)),
+            Compression::Gzip => 0..=9,
+            Compression::Zstd | Compression::ZstdDict => zstd::compression_level_range(),
+        };
+        if range.contains(&level) {
+            Ok(())
+        } else {
+            Err(format!(
+                "{:?} compression levels are {} to {}, not {}",
+                self.compression,
+                range.start(),
+                range.end(),
+                level
+            ))
+        }
+    }
+}
+
+pub fn compress(data: &[u8], options: &WriteOptions) -> Result<Vec<u8>, Box<dyn Error>> {
+    options.check_level()?;
+    match options.compression {
+        Compression::None => Ok(data.to_vec()),
+        Compression::Gzip => {
+            let level = options
+                .level
+                .map_or(GzLevel::best(), |l| GzLevel::new(l as u32));
+            let mut encoder = GzEncoder::new(Vec::new(), level);
+            encoder.write_all(data)?;
+            Ok(encoder.finish()?)
+        }
+        Compression::Zstd => {
+            let level = options.level.unwrap_or(ZSTD_DEFAULT_LEVEL);
+            Ok(zstd::encode_all(data, level)?)
+        }
+        Compression::ZstdDict => {
+            let level = options.level.unwrap_or(ZSTD_DEFAULT_LEVEL);
+            let dictionary = options
+                .dictionary
+                .as_deref()
+                .ok_or("zstd-dict compression needs a dictionary from train_dictionary")?;
+
+            let mut encoder =
+                zstd::stream::write::Encoder::with_dictionary(Vec::new(), level, dictionary)?;
+            encoder.set_pledged_src_size(Some(data.len() as u64))?;
+            // Load the dictionary into the match finder like data already
+            // seen; merely attaching it compresses a whole Bible worse than
+            // no dictionary at all
+            encoder.set_parameter(CParameter::ForceAttachDict(DictAttachPref::ForceLoad))?;
+            encoder.write_all(data)?;
+            Ok(encoder.finish()?)
+        }
+    }
+}
+
+// Verses share a lot of vocabulary ("And the LORD said unto"), which is
+// exactly what a zstd dictionary captures
+pub fn train_dictionary(bible: &Bible) -> Result<Vec<u8>, Box<dyn Error>> {
+    let samples: Vec<&[u8]> = bible
+        .books()
+        .flat_map(|b| &b.chapters)
+        .flat_map(|c| &c.verses)
+        .map(|v| v.text.as_bytes())
+        .collect();
+
+    zstd::dict::from_samples(&samples, ZSTD_DICT_SIZE)
+        .map_err(|e| format!("failed to train zstd dictionary: {}", e).into())
+}
+
+// Decompresses data written by `compress`, detecting the codec from its magic
+// bytes. Anything unrecognised is assumed to be uncompressed.
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    decompress_with(data, None)
+}
+
+// Like decompress, with the dictionary that ZstdDict output was written with
+pub fn decompress_with(data: &[u8], dictionary: Option<&[u8]>) -> Result<Vec<u8>, Box<dyn Error>> {
+    if data.starts_with(GZIP_MAGIC) {
+        let mut out = Vec::new();
+        GzDecoder::new(data).read_to_end(&mut out)?;
+        Ok(out)
+    } else if data.starts_with(ZSTD_MAGIC) {
+        let Some(id) = zstd::zstd_safe::get_dict_id_from_frame(data) else {
+            return Ok(zstd::decode_all(data)?);
+        };
+        let dictionary =
+            dictionary.ok_or_else(|| format!("data needs zstd dictionary {} to decompress", id))?;
+        if zstd::zstd_safe::get_dict_id_from_dict(dictionary) != Some(id) {
+            return Err(format!("data needs zstd dictionary {}, not the one given", id).into());
+        }
+
+        let mut out = Vec::new();
+        zstd::stream::read::Decoder::with_dictionary(data, dictionary)?.read_to_end(&mut out)?;
+        Ok(out)
+    } else {
+        Ok(data.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Book, Chapter, Verse};
+
+    // Dictionary training needs far more text than the sample Bible holds
+    fn large_bible(seed: u32) -> Bible {
+        let words = [
+            "And", "the", "LORD", "said", "unto", "Moses", "behold", "I", "will", "send", "thee",
+            "into", "land", "of", "Egypt", "people", "Israel", "that", "they", "may",
+        ];
+        let mut state = seed;
+        let mut next = move || {
+            state = state.wrapping_mul(1103515245).wrapping_add(12345);
+            (state >> 16) as usize
+        };
+
+        let chapters = (1..=50)
+            .map(|c| Chapter {
+                number: c.to_string(),
+                verses: (1..=40)
+                    .map(|v| Verse {
+                        number: v.to_string(),
+                        text: (0..10 + next() % 20)
+                            .map(|_| words[next() % words.len()])
+                            .collect::<Vec<_>>()
+                            .join(" "),
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        Bible {
+            ot_contents: vec!["Exodus".to_string()],
+            ot: vec![Book {
+                name: "Exodus".to_string(),
+                chapters,
+            }],
+            nt_contents: Vec::new(),
+            nt: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn round_trips_every_codec() {
+        let bible = large_bible(7);
+        let data = serde_json::to_vec(&bible).unwrap();
+        let dictionary = train_dictionary(&bible).unwrap();
+
+        for compression in [
+            Compression::None,
+            Compression::Gzip,
+            Compression::Zstd,
+            Compression::ZstdDict,
+        ] {
+            let options = WriteOptions {
+                compression,
+                level: None,
+                dictionary: Some(dictionary.clone()),
+            };
+            let compressed = compress(&data, &options).unwrap();
+            if compression != Compression::None {
+                assert!(
+                    compressed.len() < data.len(),
+                    "{:?} should shrink the data",
+                    compression
+                );
+            }
+            assert_eq!(
+                decompress_with(&compressed, Some(&dictionary)).unwrap(),
+                data,
+                "{:?}",
+                compression
+            );
+        }
+    }
+
+    #[test]
+    fn dictionary_beats_plain_zstd() {
+        let bible = large_bible(7);
+        let data = bincode::encode_to_vec(&bible, bincode::config::standard()).unwrap();
+        let options = |compression, dictionary| WriteOptions {
+            compression,
+            level: None,
+            dictionary,
+        };
+        let zstd = compress(&data, &options(Compression::Zstd, None)).unwrap();
+        let dictionary = train_dictionary(&bible).unwrap();
+        let dict = compress(
+            &data,
+            &options(Compression::ZstdDict, Some(dictionary.clone())),
+        )
+        .unwrap();
+        assert!(
+            dict.len() < zstd.len(),
+            "zstd-dict {} bytes, zstd {} bytes",
+            dict.len(),
+            zstd.len()
+        );
+
+        assert!(compress(&data, &options(Compression::ZstdDict, None)).is_err());
+        assert!(decompress(&dict).is_err());
+        let other = train_dictionary(&large_bible(8)).unwrap();
+        assert!(decompress_with(&dict, Some(&other)).is_err());
+        assert_eq!(decompress_with(&dict, Some(&dictionary)).unwrap(), data);
+    }
+
+    #[test]
+    fn parses_compression_names() {
+        assert_eq!("gzip".parse(), Ok(Compression::Gzip));
+        assert_eq!("zstd".parse(), Ok(Compression::Zstd));
+        assert_eq!("zstd-dict".parse(), Ok(Compression::ZstdDict));
+        assert!("brotli".parse::<Compression>().is_err());
+    }
+
+    #[test]
+    fn checks_levels_per_codec() {
+        let options = |compression, level| WriteOptions {
+            compression,
+            level: Some(level),
+            dictionary: None,
+        };
+        assert!(options(Compression::Gzip, 9).check_level().is_ok());
+        assert_eq!(
+            options(Compression::Gzip, -1).check_level(),
+            Err("Gzip compression levels are 0 to 9, not -1".to_string())
+        );
+        assert!(options(Compression::Gzip, 10).check_level().is_err());
+        assert!(options(Compression::Zstd, 22).check_level().is_ok());
+        assert!(options(Compression::ZstdDict, 23).check_level().is_err());
+        assert!(options(Compression::None, 1).check_level().is_err());
+        assert!(WriteOptions::default().check_level().is_ok());
+
+        assert!(compress(b"data", &options(Compression::Gzip, 99)).is_err());
+    }
+}
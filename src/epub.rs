@@ -0,0 +1,434 @@
+use crate::model::{Bible, Book};
+use std::error::Error;
+use std::fmt::Write as _;
+use std::io::{Seek, Write};
+use zip::CompressionMethod;
+use zip::write::{SimpleFileOptions, ZipWriter};
+
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+const STYLESHEET: &str = "h1 { text-align: center; margin: 2em 0 1em; }
+h2 { margin-top: 1.5em; font-size: 1.2em; }
+p { margin: 0.3em 0; text-indent: 0; }
+sup { font-size: 0.65em; color: #666; }
+";
+
+// Dublin Core metadata of the book, normally read from the Gutenberg header
+#[derive(Clone, Debug, PartialEq)]
+pub struct EpubMetadata {
+    pub title: String,
+    pub language: String,
+    pub identifier: String,
+    pub date: Option<String>, // YYYY-MM-DD
+    pub creator: Option<String>,
+}
+
+impl Default for EpubMetadata {
+    fn default() -> Self {
+        EpubMetadata {
+            title: "The King James Version of the Bible".to_string(),
+            language: "en".to_string(),
+            identifier: "urn:gutenberg:10".to_string(),
+            date: None,
+            creator: None,
+        }
+    }
+}
+
+// "August 1, 1989 [eBook #10]" -> "1989-08-01"
+fn iso_date(release: &str) -> Option<String> {
+    let date = release.split('[').next()?.trim();
+    let (month, rest) = date.split_once(' ')?;
+    let (day, year) = rest.split_once(',')?;
+    let month = MONTHS.iter().position(|m| *m == month)? + 1;
+    let day: u32 = day.trim().parse().ok()?;
+    let year: u32 = year.trim().parse().ok()?;
+    Some(format!("{:04}-{:02}-{:02}", year, month, day))
+}
+
+impl EpubMetadata {
+    // Reads the "Key: value" lines before the "*** START OF" marker, keeping
+    // the defaults for anything missing
+    pub fn from_gutenberg(text: &str) -> Self {
+        let mut metadata = EpubMetadata::default();
+        for line in text.lines() {
+            if line.starts_with("*** START OF") {
+                break;
+            }
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+            match key.trim() {
+                "Title" => metadata.title = value.to_string(),
+                "Author" => metadata.creator = Some(value.to_string()),
+                "Language" => {
+                    metadata.language = match value {
+                        "English" => "en".to_string(),
+                        other => other.to_string(),
+                    }
+                }
+                "Release date" | "Release Date" => {
+                    metadata.date = iso_date(value);
+                    if let Some((_, number)) = value.split_once('#') {
+                        let number = number.trim_end_matches(']').trim();
+                        metadata.identifier = format!("urn:gutenberg:{}", number);
+                    }
+                }
+                _ => {}
+            }
+        }
+        metadata
+    }
+}
+
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn book_file(index: usize) -> String {
+    format!("book{:02}.xhtml", index + 1)
+}
+
+fn xhtml_page(title: &str, language: &str, body: &str) -> String {
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<!DOCTYPE html>\n\
+         <html xmlns=\"http://www.w3.org/1999/xhtml\" xmlns:epub=\"http://www.idpf.org/2007/ops\" \
+         xml:lang=\"{}\" lang=\"{}\">\n<head>\n<meta charset=\"UTF-8\"/>\n<title>{}</title>\n\
+         <link rel=\"stylesheet\" type=\"text/css\" href=\"style.css\"/>\n</head>\n<body>\n{}</body>\n</html>\n",
+        language,
+        language,
+        escape_xml(title),
+        body
+    )
+}
+
+fn book_page(book: &Book, language: &str) -> String {
+    let mut body = format!(
+        "<section epub:type=\"chapter\">\n<h1>{}</h1>\n",
+        escape_xml(&book.name)
+    );
+    for chapter in &book.chapters {
+        writeln!(
+            body,
+            "<h2 id=\"c{}\">Chapter {}</h2>",
+            chapter.number, chapter.number
+        )
+        .unwrap();
+        for verse in &chapter.verses {
+            writeln!(
+                body,
+                "<p id=\"c{}v{}\"><sup>{}</sup> {}</p>",
+                chapter.number,
+                verse.number,
+                verse.number,
+                escape_xml(&verse.text)
+            )
+            .unwrap();
+        }
+    }
+    body.push_str("</section>\n");
+    xhtml_page(&book.name, language, &body)
+}
+
+// Table of contents with a section per testament, in the order of the
+// parsed contents lists, and every chapter under its book
+fn nav_page(bible: &Bible, metadata: &EpubMetadata) -> String {
+    let books: Vec<&Book> = bible.books().collect();
+    let mut body = String::from("<nav epub:type=\"toc\" id=\"toc\">\n<h1>Contents</h1>\n<ol>\n");
+    for (heading, contents) in [
+        ("The Old Testament", &bible.ot_contents),
+        ("The New Testament", &bible.nt_contents),
+    ] {
+        let entries: Vec<usize> = contents
+            .iter()
+            .filter_map(|name| books.iter().position(|b| b.name == *name))
+            .collect();
+        if entries.is_empty() {
+            continue;
+        }
+        writeln!(
+            body,
+            "<li><a href=\"{}\">{}</a>\n<ol>",
+            book_file(entries[0]),
+            heading
+        )
+        .unwrap();
+        for index in entries {
+            let book = books[index];
+            writeln!(
+                body,
+                "<li><a href=\"{}\">{}</a>\n<ol>",
+                book_file(index),
+                escape_xml(&book.name)
+            )
+            .unwrap();
+            for chapter in &book.chapters {
+                writeln!(
+                    body,
+                    "<li><a href=\"{}#c{}\">{} {}</a></li>",
+                    book_file(index),
+                    chapter.number,
+                    escape_xml(&book.name),
+                    chapter.number
+                )
+                .unwrap();
+            }
+            body.push_str("</ol>\n</li>\n");
+        }
+        body.push_str("</ol>\n</li>\n");
+    }
+    body.push_str("</ol>\n</nav>\n");
+    xhtml_page("Contents", &metadata.language, &body)
+}
+
+fn package_document(bible: &Bible, metadata: &EpubMetadata) -> String {
+    let mut opf = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <package xmlns=\"http://www.idpf.org/2007/opf\" version=\"3.0\" unique-identifier=\"book-id\">\n\
+         <metadata xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n",
+    );
+    writeln!(
+        opf,
+        "<dc:identifier id=\"book-id\">{}</dc:identifier>",
+        escape_xml(&metadata.identifier)
+    )
+    .unwrap();
+    writeln!(opf, "<dc:title>{}</dc:title>", escape_xml(&metadata.title)).unwrap();
+    writeln!(
+        opf,
+        "<dc:language>{}</dc:language>",
+        escape_xml(&metadata.language)
+    )
+    .unwrap();
+    if let Some(creator) = &metadata.creator {
+        writeln!(opf, "<dc:creator>{}</dc:creator>", escape_xml(creator)).unwrap();
+    }
+    if let Some(date) = &metadata.date {
+        writeln!(opf, "<dc:date>{}</dc:date>", date).unwrap();
+    }
+    // Required by EPUB 3. The release date keeps the output reproducible.
+    writeln!(
+        opf,
+        "<meta property=\"dcterms:modified\">{}T00:00:00Z</meta>",
+        metadata.date.as_deref().unwrap_or("1989-08-01")
+    )
+    .unwrap();
+    opf.push_str("</metadata>\n<manifest>\n");
+    opf.push_str(
+        "<item id=\"nav\" href=\"nav.xhtml\" media-type=\"application/xhtml+xml\" properties=\"nav\"/>\n\
+         <item id=\"css\" href=\"style.css\" media-type=\"text/css\"/>\n",
+    );
+    let count = bible.books().count();
+    for index in 0..count {
+        writeln!(
+            opf,
+            "<item id=\"book{:02}\" href=\"{}\" media-type=\"application/xhtml+xml\"/>",
+            index + 1,
+            book_file(index)
+        )
+        .unwrap();
+    }
+    opf.push_str("</manifest>\n<spine>\n<itemref idref=\"nav\"/>\n");
+    for index in 0..count {
+        writeln!(opf, "<itemref idref=\"book{:02}\"/>", index + 1).unwrap();
+    }
+    opf.push_str("</spine>\n</package>\n");
+    opf
+}
+
+const CONTAINER: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<container version=\"1.0\" xmlns=\"urn:oasis:names:tc:opendocument:xmlns:container\">
+<rootfiles>
+<rootfile full-path=\"OEBPS/content.opf\" media-type=\"application/oebps-package+xml\"/>
+</rootfiles>
+</container>
+";
+
+// Writes the EPUB container. The mimetype entry has to come first and be
+// stored uncompressed so readers can sniff it.
+pub fn build_epub<W: Write + Seek>(
+    bible: &Bible,
+    metadata: &EpubMetadata,
+    out: W,
+) -> Result<W, Box<dyn Error>> {
+    let mut zip = ZipWriter::new(out);
+    let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+    let deflated = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    zip.start_file("mimetype", stored)?;
+    zip.write_all(b"application/epub+zip")?;
+    zip.start_file("META-INF/container.xml", deflated)?;
+    zip.write_all(CONTAINER.as_bytes())?;
+    zip.start_file("OEBPS/content.opf", deflated)?;
+    zip.write_all(package_document(bible, metadata).as_bytes())?;
+    zip.start_file("OEBPS/nav.xhtml", deflated)?;
+    zip.write_all(nav_page(bible, metadata).as_bytes())?;
+    zip.start_file("OEBPS/style.css", deflated)?;
+    zip.write_all(STYLESHEET.as_bytes())?;
+    for (index, book) in bible.books().enumerate() {
+        zip.start_file(format!("OEBPS/{}", book_file(index)), deflated)?;
+        zip.write_all(book_page(book, &metadata.language).as_bytes())?;
+    }
+    Ok(zip.finish()?)
+}
+
+pub fn write_epub(
+    bible: &Bible,
+    metadata: &EpubMetadata,
+    path: &str,
+) -> Result<(), Box<dyn Error>> {
+    let file = std::fs::File::create(path)?;
+    build_epub(bible, metadata, std::io::BufWriter::new(file))?.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::sample_bible;
+    use std::collections::HashMap;
+    use std::io::{Cursor, Read};
+
+    fn unpack(data: Vec<u8>) -> (Vec<String>, HashMap<String, String>) {
+        let mut archive = zip::ZipArchive::new(Cursor::new(data)).unwrap();
+        let mut names = Vec::new();
+        let mut files = HashMap::new();
+        for i in 0..archive.len() {
+            let mut file = archive.by_index(i).unwrap();
+            if i == 0 {
+                assert_eq!(file.compression(), CompressionMethod::Stored);
+            }
+            let mut contents = String::new();
+            file.read_to_string(&mut contents).unwrap();
+            names.push(file.name().to_string());
+            files.insert(file.name().to_string(), contents);
+        }
+        (names, files)
+    }
+
+    // Values of every attr="..." in a document
+    fn attributes<'a>(xml: &'a str, attr: &str) -> Vec<&'a str> {
+        let needle = format!(" {}=\"", attr);
+        xml.match_indices(&needle)
+            .map(|(i, _)| {
+                let rest = &xml[i + needle.len()..];
+                &rest[..rest.find('"').unwrap()]
+            })
+            .collect()
+    }
+
+    // Every start tag is closed in order; enough to catch broken markup
+    fn assert_well_formed(xml: &str) {
+        let mut open = Vec::new();
+        for tag in xml.split('<').skip(1).map(|t| &t[..t.find('>').unwrap()]) {
+            if tag.starts_with('?') || tag.starts_with('!') || tag.ends_with('/') {
+                continue;
+            }
+            let name = tag.trim_start_matches('/').split(' ').next().unwrap();
+            if tag.starts_with('/') {
+                assert_eq!(open.pop(), Some(name), "in {}", xml);
+            } else {
+                open.push(name);
+            }
+        }
+        assert!(open.is_empty());
+    }
+
+    #[test]
+    fn builds_a_valid_container() {
+        let mut bible = sample_bible();
+        bible.nt[0].chapters[0].verses[0].text = "Jews & <Pharisees>".to_string();
+        let data = build_epub(&bible, &EpubMetadata::default(), Cursor::new(Vec::new()))
+            .unwrap()
+            .into_inner();
+        assert_eq!(&data[30..38], b"mimetype");
+        let (names, files) = unpack(data);
+
+        assert_eq!(names[0], "mimetype");
+        assert_eq!(files["mimetype"], "application/epub+zip");
+        assert_eq!(
+            attributes(&files["META-INF/container.xml"], "full-path"),
+            ["OEBPS/content.opf"]
+        );
+
+        let opf = &files["OEBPS/content.opf"];
+        let ids = attributes(opf, "id");
+        for href in attributes(opf, "href") {
+            assert!(files.contains_key(&format!("OEBPS/{}", href)), "{}", href);
+        }
+        for idref in attributes(opf, "idref") {
+            assert!(ids.contains(&idref), "{}", idref);
+        }
+        assert_eq!(attributes(opf, "properties"), ["nav"]);
+        assert!(opf.contains("<meta property=\"dcterms:modified\">"));
+
+        let nav = &files["OEBPS/nav.xhtml"];
+        for href in attributes(nav, "href")
+            .into_iter()
+            .filter(|h| h.contains(".xhtml"))
+        {
+            let (file, fragment) = href.split_once('#').unwrap_or((href, ""));
+            let target = &files[&format!("OEBPS/{}", file)];
+            if !fragment.is_empty() {
+                assert!(target.contains(&format!("id=\"{}\"", fragment)), "{}", href);
+            }
+        }
+
+        for (name, contents) in &files {
+            if name.ends_with(".xhtml") || name.ends_with(".opf") || name.ends_with(".xml") {
+                assert_well_formed(contents);
+            }
+        }
+    }
+
+    #[test]
+    fn renders_books_and_contents() {
+        let bible = sample_bible();
+        let data = build_epub(&bible, &EpubMetadata::default(), Cursor::new(Vec::new()))
+            .unwrap()
+            .into_inner();
+        let (names, files) = unpack(data);
+        assert_eq!(names.len(), 8);
+
+        let genesis = &files["OEBPS/book01.xhtml"];
+        assert!(genesis.contains("<h1>Genesis</h1>\n<h2 id=\"c1\">Chapter 1</h2>\n"));
+        assert!(genesis.contains("<p id=\"c2v1\"><sup>1</sup> Thus the heavens"));
+
+        let nav = &files["OEBPS/nav.xhtml"];
+        assert!(nav.contains("<li><a href=\"book03.xhtml\">The New Testament</a>\n<ol>\n<li><a href=\"book03.xhtml\">John</a>"));
+        assert!(nav.contains("<li><a href=\"book02.xhtml#c23\">Psalms 23</a></li>"));
+    }
+
+    #[test]
+    fn reads_gutenberg_header() {
+        let header = "Title: The King James Version of the Bible\n\
+                      Release date: August 1, 1989 [eBook #10]\n\
+                      Language: English\n\
+                      *** START OF THE PROJECT GUTENBERG EBOOK 10 ***\n\
+                      Title: not metadata\n";
+        let metadata = EpubMetadata::from_gutenberg(header);
+        assert_eq!(metadata.title, "The King James Version of the Bible");
+        assert_eq!(metadata.language, "en");
+        assert_eq!(metadata.identifier, "urn:gutenberg:10");
+        assert_eq!(metadata.date.as_deref(), Some("1989-08-01"));
+        assert_eq!(metadata.creator, None);
+        assert_eq!(EpubMetadata::from_gutenberg(""), EpubMetadata::default());
+    }
+}
@@ -0,0 +1,188 @@
+use crate::date::Date;
+use crate::plan::Plan;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Escapes TEXT values (RFC 5545 section 3.3.11)
+fn escape_text(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+// Content lines longer than 75 octets continue on lines starting with a space
+fn push_line(out: &mut String, line: &str) {
+    let mut rest = line;
+    let mut limit = 75;
+    while rest.len() > limit {
+        let mut split = limit;
+        while !rest.is_char_boundary(split) {
+            split -= 1;
+        }
+        out.push_str(&rest[..split]);
+        out.push_str("\r\n ");
+        rest = &rest[split..];
+        limit = 74;
+    }
+    out.push_str(rest);
+    out.push_str("\r\n");
+}
+
+fn compact(date: Date) -> String {
+    format!("{:04}{:02}{:02}", date.year, date.month, date.day)
+}
+
+// DTSTAMP value (RFC 5545 section 3.8.7.2): when the file was generated, in UTC
+fn timestamp(time: SystemTime) -> String {
+    let secs = time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let date = Date::from_days_since_epoch((secs / 86_400) as i64);
+    let secs = secs % 86_400;
+    format!(
+        "{}T{:02}{:02}{:02}Z",
+        compact(date),
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60
+    )
+}
+
+// An iCalendar file with an all-day event per day of a plan from crate::plan,
+// starting on `start`, with a line per track in the description when there
+// are several. `generated` is the DTSTAMP of every event, normally
+// SystemTime::now(). Event UIDs depend only on the plan name, start date and
+// day, so importing the file again updates the events instead of duplicating
+// them.
+pub fn plan_calendar(plan: &Plan, name: &str, start: Date, generated: SystemTime) -> String {
+    let mut out = String::new();
+    for line in [
+        "BEGIN:VCALENDAR",
+        "VERSION:2.0",
+        "PRODID:-//parse-bible//Reading plan//EN",
+        "CALSCALE:GREGORIAN",
+    ] {
+        push_line(&mut out, line);
+    }
+    push_line(&mut out, &format!("X-WR-CALNAME:{}", escape_text(name)));
+
+    let slug: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    let stamp = timestamp(generated);
+    for day in 0..plan.days {
+        let date = start.add_days(day as i64);
+        let summary = plan.day_summary(day);
+        let mut description = String::new();
+        if plan.tracks.len() > 1 {
+            for track in &plan.tracks {
+                let references = track.readings[day].references.join("; ");
+                description.push_str(&format!("{}: {}\n", track.name, references));
+            }
+        } else {
+            description.push_str(&format!("{}\n", summary));
+        }
+        description.push_str(&format!("{} words", plan.day_words(day)));
+
+        push_line(&mut out, "BEGIN:VEVENT");
+        push_line(
+            &mut out,
+            &format!("UID:{}-{}-day{}@parse-bible", slug, compact(start), day + 1),
+        );
+        push_line(&mut out, &format!("DTSTAMP:{}", stamp));
+        push_line(&mut out, &format!("DTSTART;VALUE=DATE:{}", compact(date)));
+        push_line(
+            &mut out,
+            &format!("DTEND;VALUE=DATE:{}", compact(date.add_days(1))),
+        );
+        push_line(
+            &mut out,
+            &format!(
+                "SUMMARY:{}",
+                escape_text(&format!("Day {}: {}", day + 1, summary))
+            ),
+        );
+        push_line(
+            &mut out,
+            &format!("DESCRIPTION:{}", escape_text(&description)),
+        );
+        push_line(&mut out, "TRANSP:TRANSPARENT");
+        push_line(&mut out, "END:VEVENT");
+    }
+    push_line(&mut out, "END:VCALENDAR");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::sample_bible;
+    use crate::plan::{PlanOptions, build_plan};
+    use std::time::Duration;
+
+    // 2025-01-01 12:34:56 UTC
+    fn generated() -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(1_735_734_896)
+    }
+
+    #[test]
+    fn writes_all_day_events() {
+        let bible = sample_bible();
+        let options = PlanOptions {
+            days: 2,
+            ..PlanOptions::default()
+        };
+        let plan = build_plan(&bible, &options).unwrap();
+        let start = Date::new(2024, 12, 31).unwrap();
+        let ics = plan_calendar(&plan, "Bible in a year", start, generated());
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(ics.ends_with("END:VEVENT\r\nEND:VCALENDAR\r\n"));
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 2);
+        assert!(ics.contains(&format!(
+            "BEGIN:VEVENT\r\nUID:bible-in-a-year-20241231-day2@parse-bible\r\n\
+             DTSTAMP:20250101T123456Z\r\nDTSTART;VALUE=DATE:20250101\r\n\
+             DTEND;VALUE=DATE:20250102\r\nSUMMARY:Day 2: Psalms 23\\; John 3\r\n\
+             DESCRIPTION:Psalms 23\\; John 3\\n{} words\r\n",
+            plan.day_words(1)
+        )));
+        assert!(ics.lines().all(|line| line.len() <= 75));
+    }
+
+    #[test]
+    fn describes_each_track_of_a_plan() {
+        let bible = sample_bible();
+        let options = PlanOptions {
+            days: 1,
+            parallel: true,
+            ..PlanOptions::default()
+        };
+        let plan = build_plan(&bible, &options).unwrap();
+        let ics = plan_calendar(&plan, "Both", Date::new(2025, 1, 1).unwrap(), generated());
+        let unfolded = ics.replace("\r\n ", "");
+        assert!(unfolded.contains(&format!(
+            "SUMMARY:Day 1: Genesis 1-2\\; Psalms 23\\; John 3\r\n\
+             DESCRIPTION:Old Testament: Genesis 1-2\\; Psalms 23\\nNew Testament: John 3\\n{} words\r\n",
+            plan.day_words(0)
+        )));
+    }
+
+    #[test]
+    fn folds_long_lines() {
+        let mut out = String::new();
+        push_line(&mut out, &"x".repeat(160));
+        assert_eq!(
+            out,
+            format!(
+                "{}\r\n {}\r\n {}\r\n",
+                "x".repeat(75),
+                "x".repeat(74),
+                "x".repeat(11)
+            )
+        );
+    }
+}
@@ -0,0 +1,137 @@
+use crate::compression;
+use crate::compression::WriteOptions;
+use crate::model::Bible;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+pub fn write_bible_to_bin(bible: &Bible, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    write_bible_to_bin_with(bible, path, &WriteOptions::default())
+}
+
+pub fn write_bible_to_bin_with(
+    bible: &Bible,
+    path: &str,
+    options: &WriteOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let config = bincode::config::standard();
+    let data = bincode::encode_to_vec(bible, config)?;
+    write_compressed(&data, path, options)
+}
+
+pub fn write_bible_to_json(bible: &Bible, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    write_bible_to_json_with(bible, path, &WriteOptions::default())
+}
+
+pub fn write_bible_to_json_with(
+    bible: &Bible,
+    path: &str,
+    options: &WriteOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let data = serde_json::to_vec_pretty(bible)?;
+    write_compressed(&data, path, options)
+}
+
+fn write_compressed(
+    data: &[u8],
+    path: &str,
+    options: &WriteOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Compress first so that a bad level or failed dictionary training
+    // leaves an existing file untouched
+    let compressed = compression::compress(data, options)?;
+    let f = File::create(path)?;
+    let mut writer = BufWriter::new(f);
+    writer.write_all(&compressed)?;
+
+    // CRITICAL: Flush the buffer before dropping
+    writer.flush()?;
+
+    Ok(())
+}
+
+// Loaders accept both plain and compressed output of the writers above
+pub fn read_bible_from_bin(path: &str) -> Result<Bible, Box<dyn std::error::Error>> {
+    read_bible_from_bin_with(path, None)
+}
+
+// The _with loaders also take the dictionary zstd-dict output was written with
+pub fn read_bible_from_bin_with(
+    path: &str,
+    dictionary: Option<&[u8]>,
+) -> Result<Bible, Box<dyn std::error::Error>> {
+    let data = compression::decompress_with(&std::fs::read(path)?, dictionary)?;
+    Ok(crate::decode_bible(&data)?)
+}
+
+pub fn read_bible_from_json(path: &str) -> Result<Bible, Box<dyn std::error::Error>> {
+    read_bible_from_json_with(path, None)
+}
+
+pub fn read_bible_from_json_with(
+    path: &str,
+    dictionary: Option<&[u8]>,
+) -> Result<Bible, Box<dyn std::error::Error>> {
+    let data = compression::decompress_with(&std::fs::read(path)?, dictionary)?;
+    Ok(serde_json::from_slice(&data)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compression::Compression;
+    use crate::model::sample_bible;
+
+    #[test]
+    fn loaders_read_compressed_writer_output() {
+        let bible = sample_bible();
+        let dir = std::env::temp_dir();
+
+        for compression in [Compression::None, Compression::Gzip, Compression::Zstd] {
+            let options = WriteOptions {
+                compression,
+                level: None,
+                dictionary: None,
+            };
+            let bin = dir.join(format!("kjv-{}-{:?}.bin", std::process::id(), compression));
+            let json = dir.join(format!("kjv-{}-{:?}.json", std::process::id(), compression));
+            let (bin, json) = (bin.to_str().unwrap(), json.to_str().unwrap());
+
+            write_bible_to_bin_with(&bible, bin, &options).unwrap();
+            write_bible_to_json_with(&bible, json, &options).unwrap();
+
+            for loaded in [
+                read_bible_from_bin(bin).unwrap(),
+                read_bible_from_json(json).unwrap(),
+            ] {
+                assert_eq!(
+                    loaded.verse("John", "3", "1").map(|v| v.text.as_str()),
+                    bible.verse("John", "3", "1").map(|v| v.text.as_str())
+                );
+                assert_eq!(loaded.ot_contents, bible.ot_contents);
+            }
+
+            std::fs::remove_file(bin).unwrap();
+            std::fs::remove_file(json).unwrap();
+        }
+    }
+
+    #[test]
+    fn bad_level_leaves_existing_file_alone() {
+        let bible = sample_bible();
+        let path = std::env::temp_dir().join(format!("kjv-{}-keep.bin", std::process::id()));
+        let path = path.to_str().unwrap();
+        write_bible_to_bin(&bible, path).unwrap();
+
+        let options = WriteOptions {
+            compression: Compression::Gzip,
+            level: Some(12),
+            dictionary: None,
+        };
+        assert!(write_bible_to_bin_with(&bible, path, &options).is_err());
+        assert_eq!(
+            read_bible_from_bin(path).unwrap().ot_contents,
+            bible.ot_contents
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,189 @@
+use crate::model::{Bible, Verse};
+use crate::reference::VerseRef;
+use std::error::Error;
+use std::fmt::Write as _;
+
+// Two columns per book and drop-cap chapter numbers. The running header
+// shows the first chapter on left pages and the last one on right pages.
+const PREAMBLE: &str = r"\documentclass[10pt,twoside]{article}
+\usepackage[T1]{fontenc}
+\usepackage[utf8]{inputenc}
+\usepackage[a5paper,margin=15mm,headsep=5mm]{geometry}
+\usepackage{multicol}
+\usepackage{lettrine}
+\usepackage{fancyhdr}
+\setlength{\columnsep}{5mm}
+\setlength{\parindent}{0pt}
+\pagestyle{fancy}
+\fancyhf{}
+\fancyhead[LE]{\rightmark}
+\fancyhead[RO]{\leftmark}
+\fancyfoot[C]{\thepage}
+\renewcommand{\headrulewidth}{0.4pt}
+\newcommand{\kjvbook}[1]{\clearpage\section*{#1}}
+\newcommand{\kjvchapter}[2]{\markboth{#1 #2}{#1 #2}\par\lettrine[lines=2]{#2}{}}
+\newcommand{\kjvverse}[1]{\textsuperscript{#1}\,}
+";
+
+fn escape_latex(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str(r"\textbackslash{}"),
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '~' => out.push_str(r"\textasciitilde{}"),
+            '^' => out.push_str(r"\textasciicircum{}"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+// One chapter's verses after the drop cap. Verse 1 takes its number from the
+// drop cap; passages starting later keep their superscript.
+fn write_chapter(out: &mut String, book: &str, chapter: &str, verses: &[&Verse]) {
+    writeln!(out, "\\kjvchapter{{{}}}{{{}}}", escape_latex(book), chapter).unwrap();
+    for verse in verses {
+        if verse.number != "1" {
+            write!(out, "\\kjvverse{{{}}}", verse.number).unwrap();
+        }
+        writeln!(out, "{}", escape_latex(&verse.text)).unwrap();
+    }
+    out.push('\n');
+}
+
+fn write_book_start(out: &mut String, book: &str) {
+    writeln!(out, "\\kjvbook{{{}}}", escape_latex(book)).unwrap();
+    out.push_str("\\begin{multicols}{2}\n");
+}
+
+// A compilable document with the whole Bible, or only the given chapter or
+// verses when a reference is passed
+pub fn latex_document(
+    bible: &Bible,
+    reference: Option<&VerseRef>,
+) -> Result<String, Box<dyn Error>> {
+    let mut out = String::from(PREAMBLE);
+    out.push_str("\\begin{document}\n");
+    match reference {
+        Some(reference) => {
+            let verses = bible.lookup(reference)?;
+            write_book_start(&mut out, reference.book);
+            write_chapter(
+                &mut out,
+                reference.book,
+                &reference.chapter.to_string(),
+                &verses,
+            );
+            out.push_str("\\end{multicols}\n");
+        }
+        None => {
+            for book in bible.books() {
+                write_book_start(&mut out, &book.name);
+                for chapter in &book.chapters {
+                    let verses: Vec<&Verse> = chapter.verses.iter().collect();
+                    write_chapter(&mut out, &book.name, &chapter.number, &verses);
+                }
+                out.push_str("\\end{multicols}\n");
+            }
+        }
+    }
+    out.push_str("\\end{document}\n");
+    Ok(out)
+}
+
+// Just the passage, for \input into another document. Only needs plain
+// LaTeX, so none of the document's packages or macros are required.
+pub fn latex_passage(bible: &Bible, reference: &VerseRef) -> Result<String, Box<dyn Error>> {
+    let verses = bible.lookup(reference)?;
+    let mut out = format!("% {}\n", reference);
+    for verse in &verses {
+        if !reference.is_single_verse() {
+            write!(out, "\\textsuperscript{{{}}}\\,", verse.number).unwrap();
+        }
+        writeln!(out, "{}", escape_latex(&verse.text)).unwrap();
+    }
+    writeln!(
+        out,
+        "\\hfill\\emph{{{}}}",
+        escape_latex(&reference.to_string())
+    )
+    .unwrap();
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::sample_bible;
+
+    fn assert_balanced(tex: &str) {
+        let mut depth = 0i32;
+        let mut escaped = false;
+        for c in tex.chars() {
+            match c {
+                '{' if !escaped => depth += 1,
+                '}' if !escaped => depth -= 1,
+                _ => {}
+            }
+            assert!(depth >= 0);
+            escaped = c == '\\' && !escaped;
+        }
+        assert_eq!(depth, 0);
+        assert_eq!(
+            tex.matches("\\begin{").count(),
+            tex.matches("\\end{").count()
+        );
+    }
+
+    #[test]
+    fn typesets_the_whole_bible() {
+        let bible = sample_bible();
+        let tex = latex_document(&bible, None).unwrap();
+        assert!(tex.starts_with("\\documentclass"));
+        assert!(tex.ends_with("\\end{document}\n"));
+        assert_balanced(&tex);
+
+        assert_eq!(tex.matches("\\kjvbook{").count(), 3);
+        assert_eq!(tex.matches("\\begin{multicols}{2}").count(), 3);
+        assert!(tex.contains(
+            "\\kjvchapter{Genesis}{1}\nIn the beginning God created the heaven and the earth.\n\
+             \\kjvverse{2}And the earth"
+        ));
+        assert!(tex.contains("\\kjvchapter{Psalms}{23}\n"));
+    }
+
+    #[test]
+    fn typesets_passages() {
+        let bible = sample_bible();
+        let reference = VerseRef::parse("John 3:2").unwrap();
+        let tex = latex_document(&bible, Some(&reference)).unwrap();
+        assert_balanced(&tex);
+        assert!(tex.contains("\\kjvbook{John}\n\\begin{multicols}{2}\n\\kjvchapter{John}{3}\n\\kjvverse{2}The same came"));
+        assert!(!tex.contains("Nicodemus"));
+
+        let reference = VerseRef::parse("Gen 1:2-3").unwrap();
+        let passage = latex_passage(&bible, &reference).unwrap();
+        assert!(passage.starts_with("% Genesis 1:2-3\n\\textsuperscript{2}\\,And the earth"));
+        assert!(passage.contains("\n\\textsuperscript{3}\\,And God said"));
+        assert!(passage.ends_with("\\hfill\\emph{Genesis 1:2-3}\n"));
+
+        let reference = VerseRef::parse("Ps 23:1").unwrap();
+        assert_eq!(
+            latex_passage(&bible, &reference).unwrap(),
+            "% Psalms 23:1\nThe LORD is my shepherd; I shall not want.\n\\hfill\\emph{Psalms 23:1}\n"
+        );
+        assert!(latex_passage(&bible, &VerseRef::parse("John 3:9").unwrap()).is_err());
+    }
+
+    #[test]
+    fn escapes_special_characters() {
+        assert_eq!(
+            escape_latex(r"50% & $5 #1 a_b {x} ~ ^ \"),
+            r"50\% \& \$5 \#1 a\_b \{x\} \textasciitilde{} \textasciicircum{} \textbackslash{}"
+        );
+    }
+}
@@ -0,0 +1,79 @@
+// The data model and lookups only need `alloc`; parsing, file I/O and the
+// output formats are behind the default `std` feature
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "anki")]
+pub mod anki;
+#[cfg(feature = "std")]
+pub mod archive;
+#[cfg(feature = "std")]
+pub mod codegen;
+#[cfg(feature = "std")]
+pub mod compression;
+pub mod date;
+#[cfg(feature = "std")]
+pub mod epub;
+#[cfg(feature = "std")]
+pub mod ics;
+#[cfg(feature = "std")]
+mod io;
+#[cfg(feature = "std")]
+pub mod latex;
+pub mod linkify;
+#[cfg(feature = "cli")]
+pub mod lsp;
+#[cfg(feature = "std")]
+pub mod mdbook;
+mod model;
+#[cfg(feature = "std")]
+pub mod obsidian;
+#[cfg(feature = "std")]
+pub mod pandoc;
+#[cfg(feature = "std")]
+mod parser;
+#[cfg(feature = "std")]
+pub mod plan;
+#[cfg(feature = "std")]
+pub mod random;
+#[cfg(feature = "cli")]
+pub mod reader;
+pub mod reference;
+#[cfg(feature = "cli")]
+pub mod repl;
+#[cfg(feature = "std")]
+pub mod rpc;
+pub mod search;
+#[cfg(feature = "cli")]
+pub mod server;
+#[cfg(feature = "std")]
+pub mod site;
+#[cfg(feature = "std")]
+pub mod tree;
+
+#[cfg(feature = "std")]
+pub use archive::{ArchivedBible, MappedBible, write_bible_to_archive};
+#[cfg(feature = "std")]
+pub use codegen::{generate_static_module, write_static_module};
+#[cfg(feature = "std")]
+pub use compression::{Compression, WriteOptions};
+pub use date::Date;
+#[cfg(feature = "std")]
+pub use io::{
+    read_bible_from_bin, read_bible_from_bin_with, read_bible_from_json, read_bible_from_json_with,
+    write_bible_to_bin, write_bible_to_bin_with, write_bible_to_json, write_bible_to_json_with,
+};
+pub use linkify::{LinkFormat, linkify};
+pub use model::{Bible, Book, Chapter, Verse};
+#[cfg(feature = "std")]
+pub use parser::parse_gutenberg;
+pub use reference::{ReferenceError, ReferenceMatch, VerseRef, find_references};
+pub use search::SearchHit;
+
+// Decodes the (uncompressed) output of write_bible_to_bin
+pub fn decode_bible(data: &[u8]) -> Result<Bible, bincode::error::DecodeError> {
+    let config = bincode::config::standard();
+    let (bible, _): (Bible, usize) = bincode::decode_from_slice(data, config)?;
+    Ok(bible)
+}
@@ -0,0 +1,137 @@
+use crate::reference::{ReferenceMatch, find_references};
+use alloc::format;
+use alloc::string::{String, ToString};
+use core::str::FromStr;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LinkFormat {
+    #[default]
+    Html,
+    Markdown,
+    // <reference osisRef="..."> elements, for OSIS XML documents
+    Osis,
+}
+
+impl FromStr for LinkFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "html" => Ok(LinkFormat::Html),
+            "markdown" | "md" => Ok(LinkFormat::Markdown),
+            "osis" => Ok(LinkFormat::Osis),
+            _ => Err(format!(
+                "unknown link format '{}' (expected html, markdown or osis)",
+                s
+            )),
+        }
+    }
+}
+
+// Link target for HTML and Markdown. "{reference}" is replaced by the
+// canonical reference ("1 Corinthians 13:4") and "{osis}" by its OSIS ID.
+pub const DEFAULT_URL_TEMPLATE: &str =
+    "https://www.biblegateway.com/passage/?search={reference}&version=KJV";
+
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// Percent-encodes everything but unreserved URL characters
+fn encode_url_component(s: &str) -> String {
+    let mut out = String::new();
+    for byte in s.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~' | b':') {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    out
+}
+
+fn url_for(m: &ReferenceMatch, template: &str) -> String {
+    template
+        .replace(
+            "{reference}",
+            &encode_url_component(&m.reference.to_string()),
+        )
+        .replace("{osis}", &encode_url_component(&m.reference.osis_ref()))
+}
+
+// Plain text between references, escaped for the markup formats
+fn push_text(out: &mut String, text: &str, format: LinkFormat) {
+    match format {
+        LinkFormat::Html | LinkFormat::Osis => out.push_str(&escape_xml(text)),
+        LinkFormat::Markdown => out.push_str(text),
+    }
+}
+
+// The plain text with every reference found by find_references turned into a
+// link. For HTML and OSIS the rest of the text is escaped as well, so the
+// result is well-formed markup.
+pub fn linkify(text: &str, format: LinkFormat, url_template: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut copied = 0;
+    for m in find_references(text) {
+        let original = &text[m.start..m.end];
+        push_text(&mut out, &text[copied..m.start], format);
+        out.push_str(&match format {
+            LinkFormat::Html => format!(
+                "<a href=\"{}\" title=\"{}\">{}</a>",
+                escape_xml(&url_for(&m, url_template)),
+                escape_xml(&m.reference.to_string()),
+                escape_xml(original)
+            ),
+            LinkFormat::Markdown => format!("[{}]({})", original, url_for(&m, url_template)),
+            LinkFormat::Osis => format!(
+                "<reference osisRef=\"{}\">{}</reference>",
+                m.reference.osis_ref(),
+                escape_xml(original)
+            ),
+        });
+        copied = m.end;
+    }
+    push_text(&mut out, &text[copied..], format);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "see Gen. 1:1 and Ps 119:105-106 & more";
+
+    #[test]
+    fn links_references_in_each_format() {
+        assert_eq!(
+            linkify(TEXT, LinkFormat::Markdown, "/kjv/{osis}"),
+            "see [Gen. 1:1](/kjv/Gen.1.1) and [Ps 119:105-106](/kjv/Ps.119.105-Ps.119.106) & more"
+        );
+        assert_eq!(
+            linkify(TEXT, LinkFormat::Osis, DEFAULT_URL_TEMPLATE),
+            "see <reference osisRef=\"Gen.1.1\">Gen. 1:1</reference> and \
+             <reference osisRef=\"Ps.119.105-Ps.119.106\">Ps 119:105-106</reference> &amp; more"
+        );
+        assert_eq!(
+            linkify("1 John 4:8", LinkFormat::Html, DEFAULT_URL_TEMPLATE),
+            "<a href=\"https://www.biblegateway.com/passage/?search=1%20John%204:8&amp;version=KJV\" \
+             title=\"1 John 4:8\">1 John 4:8</a>"
+        );
+        assert_eq!(
+            linkify("x < y, John 3:16", LinkFormat::Html, "/{osis}"),
+            "x &lt; y, <a href=\"/John.3.16\" title=\"John 3:16\">John 3:16</a>"
+        );
+    }
+
+    #[test]
+    fn leaves_text_without_references_alone() {
+        let text = "Nothing to see on page 4, chapter 2.";
+        assert_eq!(linkify(text, LinkFormat::Html, DEFAULT_URL_TEMPLATE), text);
+        assert!("rtf".parse::<LinkFormat>().is_err());
+        assert_eq!("md".parse(), Ok(LinkFormat::Markdown));
+    }
+}
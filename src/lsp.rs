@@ -0,0 +1,446 @@
+use crate::model::Bible;
+use crate::reference::{
+    ReferenceMatch, VerseRef, book_names, find_references, normalize_book, osis_id, resolve_book,
+};
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::{
+    CodeAction, CodeActionKind, CodeActionOrCommand, CodeActionParams,
+    CodeActionProviderCapability, CompletionItem, CompletionItemKind, CompletionOptions,
+    CompletionParams, CompletionTextEdit, Diagnostic, DiagnosticSeverity,
+    DidChangeTextDocumentParams, DidCloseTextDocumentParams, DidOpenTextDocumentParams, Hover,
+    HoverContents, HoverParams, HoverProviderCapability, MarkupContent, MarkupKind, Position,
+    PublishDiagnosticsParams, Range, ServerCapabilities, TextDocumentSyncCapability,
+    TextDocumentSyncKind, TextEdit, Uri, WorkspaceEdit,
+};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::error::Error;
+
+// LSP positions count UTF-16 code units within a line
+fn offset_at(text: &str, position: Position) -> usize {
+    let mut line_start = 0;
+    for _ in 0..position.line {
+        match text[line_start..].find('\n') {
+            Some(p) => line_start += p + 1,
+            None => return text.len(),
+        }
+    }
+    let line_end = text[line_start..]
+        .find('\n')
+        .map_or(text.len(), |p| line_start + p);
+    let mut units = 0;
+    for (i, c) in text[line_start..line_end].char_indices() {
+        if units >= position.character {
+            return line_start + i;
+        }
+        units += c.len_utf16() as u32;
+    }
+    line_end
+}
+
+fn position_at(text: &str, offset: usize) -> Position {
+    let before = &text[..offset];
+    let line_start = before.rfind('\n').map_or(0, |p| p + 1);
+    Position::new(
+        before.matches('\n').count() as u32,
+        before[line_start..].encode_utf16().count() as u32,
+    )
+}
+
+fn range_of(text: &str, m: &ReferenceMatch) -> Range {
+    Range::new(position_at(text, m.start), position_at(text, m.end))
+}
+
+// The book named by the last one to three words of `before`, if any
+fn book_before(before: &str) -> Option<&'static str> {
+    let words: Vec<&str> = before.split(' ').collect();
+    (1..=3.min(words.len())).rev().find_map(|n| {
+        let name = words[words.len() - n..].join(" ");
+        resolve_book(name.trim_start_matches(|c: char| !c.is_alphanumeric()))
+    })
+}
+
+pub struct LanguageServer {
+    bible: Bible,
+    documents: HashMap<Uri, String>,
+}
+
+impl LanguageServer {
+    pub fn new(bible: Bible) -> Self {
+        LanguageServer {
+            bible,
+            documents: HashMap::new(),
+        }
+    }
+
+    pub fn capabilities() -> ServerCapabilities {
+        ServerCapabilities {
+            text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+            hover_provider: Some(HoverProviderCapability::Simple(true)),
+            completion_provider: Some(CompletionOptions {
+                trigger_characters: Some(vec![" ".to_string(), ":".to_string()]),
+                ..CompletionOptions::default()
+            }),
+            code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
+            ..ServerCapabilities::default()
+        }
+    }
+
+    fn quote(&self, reference: &VerseRef) -> Option<String> {
+        let verses = self.bible.lookup(reference).ok()?;
+        let texts: Vec<&str> = verses.iter().map(|v| v.text.as_str()).collect();
+        Some(texts.join(" "))
+    }
+
+    pub fn diagnostics(&self, text: &str) -> Vec<Diagnostic> {
+        find_references(text)
+            .iter()
+            .filter_map(|m| {
+                let e = self.bible.lookup(&m.reference).err()?;
+                Some(Diagnostic {
+                    range: range_of(text, m),
+                    severity: Some(DiagnosticSeverity::ERROR),
+                    source: Some("kjv".to_string()),
+                    message: e.to_string(),
+                    ..Diagnostic::default()
+                })
+            })
+            .collect()
+    }
+
+    pub fn hover(&self, text: &str, position: Position) -> Option<Hover> {
+        let offset = offset_at(text, position);
+        let m = find_references(text)
+            .into_iter()
+            .find(|m| m.start <= offset && offset <= m.end)?;
+
+        let mut value = format!("**{}**\n\n", m.reference);
+        match self.bible.lookup(&m.reference) {
+            Ok(verses) => {
+                for verse in verses {
+                    value.push_str(&format!("<sup>{}</sup> {}\n", verse.number, verse.text));
+                }
+            }
+            Err(e) => value.push_str(&e.to_string()),
+        }
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value,
+            }),
+            range: Some(range_of(text, &m)),
+        })
+    }
+
+    // Book names while typing a name, chapter numbers after "John ", and
+    // verse numbers after "John 3:"
+    pub fn completions(&self, text: &str, position: Position) -> Vec<CompletionItem> {
+        let offset = offset_at(text, position);
+        let line_start = text[..offset].rfind('\n').map_or(0, |p| p + 1);
+        let prefix = &text[line_start..offset];
+        let (before, last) = prefix.rsplit_once(' ').unwrap_or(("", prefix));
+
+        if let Some((chapter, _)) = last.split_once(':') {
+            let Some(chapter) = book_before(before).and_then(|b| self.bible.chapter(b, chapter))
+            else {
+                return Vec::new();
+            };
+            return chapter
+                .verses
+                .iter()
+                .map(|v| CompletionItem {
+                    label: v.number.clone(),
+                    kind: Some(CompletionItemKind::VALUE),
+                    detail: Some(v.text.clone()),
+                    ..CompletionItem::default()
+                })
+                .collect();
+        }
+
+        if last.chars().all(|c| c.is_ascii_digit()) {
+            let Some(book) = book_before(before).and_then(|b| self.bible.book(b)) else {
+                return Vec::new();
+            };
+            return book
+                .chapters
+                .iter()
+                .map(|c| CompletionItem {
+                    label: c.number.clone(),
+                    kind: Some(CompletionItemKind::VALUE),
+                    detail: Some(format!(
+                        "{} {} ({} verses)",
+                        book.name,
+                        c.number,
+                        c.verses.len()
+                    )),
+                    ..CompletionItem::default()
+                })
+                .collect();
+        }
+
+        // Include a leading "1", "I" or "First" so "1 Co" completes to 1 Corinthians
+        let word_start = offset
+            - last
+                .trim_start_matches(|c: char| !c.is_alphanumeric())
+                .len();
+        let partial_start = match before.rsplit(' ').next() {
+            Some(n @ ("1" | "2" | "3" | "I" | "II" | "III" | "First" | "Second" | "Third")) => {
+                word_start - n.len() - 1
+            }
+            _ => word_start,
+        };
+        let partial = normalize_book(&text[partial_start..offset]);
+        let range = Range::new(position_at(text, partial_start), position);
+        book_names()
+            .filter(|name| {
+                normalize_book(name).starts_with(&partial)
+                    || osis_id(name).is_some_and(|osis| normalize_book(osis).starts_with(&partial))
+            })
+            .map(|name| CompletionItem {
+                label: name.to_string(),
+                kind: Some(CompletionItemKind::MODULE),
+                text_edit: Some(CompletionTextEdit::Edit(TextEdit::new(
+                    range,
+                    name.to_string(),
+                ))),
+                ..CompletionItem::default()
+            })
+            .collect()
+    }
+
+    // Offers to insert the quoted verse text after each reference in range
+    pub fn code_actions(&self, uri: &Uri, text: &str, range: Range) -> Vec<CodeActionOrCommand> {
+        let (start, end) = (offset_at(text, range.start), offset_at(text, range.end));
+        find_references(text)
+            .iter()
+            .filter(|m| m.start <= end && start <= m.end)
+            .filter_map(|m| {
+                let quote = self.quote(&m.reference)?;
+                let at = position_at(text, m.end);
+                let edit = TextEdit::new(Range::new(at, at), format!(" \"{}\"", quote));
+                Some(CodeActionOrCommand::CodeAction(CodeAction {
+                    title: format!("Insert text of {}", m.reference),
+                    kind: Some(CodeActionKind::REFACTOR_INLINE),
+                    edit: Some(WorkspaceEdit {
+                        changes: Some(HashMap::from([(uri.clone(), vec![edit])])),
+                        ..WorkspaceEdit::default()
+                    }),
+                    ..CodeAction::default()
+                }))
+            })
+            .collect()
+    }
+
+    fn document(&self, uri: &Uri) -> &str {
+        self.documents.get(uri).map_or("", String::as_str)
+    }
+
+    pub fn handle_request(&self, request: Request) -> Response {
+        fn params<P: DeserializeOwned>(request: &Request) -> Result<P, Response> {
+            serde_json::from_value(request.params.clone()).map_err(|e| {
+                Response::new_err(
+                    request.id.clone(),
+                    ErrorCode::InvalidParams as i32,
+                    e.to_string(),
+                )
+            })
+        }
+
+        let result = match request.method.as_str() {
+            "textDocument/hover" => params::<HoverParams>(&request).map(|p| {
+                let doc = p.text_document_position_params;
+                serde_json::to_value(
+                    self.hover(self.document(&doc.text_document.uri), doc.position),
+                )
+            }),
+            "textDocument/completion" => params::<CompletionParams>(&request).map(|p| {
+                let doc = p.text_document_position;
+                serde_json::to_value(
+                    self.completions(self.document(&doc.text_document.uri), doc.position),
+                )
+            }),
+            "textDocument/codeAction" => params::<CodeActionParams>(&request).map(|p| {
+                let uri = &p.text_document.uri;
+                serde_json::to_value(self.code_actions(uri, self.document(uri), p.range))
+            }),
+            _ => Err(Response::new_err(
+                request.id.clone(),
+                ErrorCode::MethodNotFound as i32,
+                format!("unhandled method {}", request.method),
+            )),
+        };
+        match result {
+            Ok(value) => Response::new_ok(request.id, value.unwrap_or(Value::Null)),
+            Err(response) => response,
+        }
+    }
+
+    // Tracks open documents; returns the diagnostics to publish, if any
+    pub fn handle_notification(&mut self, notification: Notification) -> Option<Notification> {
+        let uri = match notification.method.as_str() {
+            "textDocument/didOpen" => {
+                let p: DidOpenTextDocumentParams =
+                    serde_json::from_value(notification.params).ok()?;
+                self.documents
+                    .insert(p.text_document.uri.clone(), p.text_document.text);
+                p.text_document.uri
+            }
+            "textDocument/didChange" => {
+                let p: DidChangeTextDocumentParams =
+                    serde_json::from_value(notification.params).ok()?;
+                // Full sync, so the last change holds the whole document
+                let text = p.content_changes.into_iter().last()?.text;
+                self.documents.insert(p.text_document.uri.clone(), text);
+                p.text_document.uri
+            }
+            "textDocument/didClose" => {
+                let p: DidCloseTextDocumentParams =
+                    serde_json::from_value(notification.params).ok()?;
+                self.documents.remove(&p.text_document.uri);
+                p.text_document.uri
+            }
+            _ => return None,
+        };
+
+        let params = PublishDiagnosticsParams {
+            diagnostics: self.diagnostics(self.document(&uri)),
+            uri,
+            version: None,
+        };
+        Some(Notification::new(
+            "textDocument/publishDiagnostics".to_string(),
+            params,
+        ))
+    }
+}
+
+// Speaks LSP over stdin/stdout until the client shuts the server down
+pub fn run_lsp(bible: Bible) -> Result<(), Box<dyn Error>> {
+    let (connection, io_threads) = Connection::stdio();
+    connection.initialize(serde_json::to_value(LanguageServer::capabilities())?)?;
+    let mut server = LanguageServer::new(bible);
+
+    for message in &connection.receiver {
+        match message {
+            Message::Request(request) => {
+                if connection.handle_shutdown(&request)? {
+                    break;
+                }
+                connection
+                    .sender
+                    .send(server.handle_request(request).into())?;
+            }
+            Message::Notification(notification) => {
+                if let Some(reply) = server.handle_notification(notification) {
+                    connection.sender.send(reply.into())?;
+                }
+            }
+            Message::Response(_) => {}
+        }
+    }
+    // The writer thread only finishes once the connection is gone
+    drop(connection);
+    io_threads.join()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::sample_bible;
+    use serde_json::json;
+
+    const DOC: &str =
+        "Sermon notes\nRead Psalm 23:1 and Gen 1:3.\nSee also Genesis 9:1 and John 3:7.";
+
+    #[test]
+    fn reports_unresolvable_references() {
+        let server = LanguageServer::new(sample_bible());
+        let diagnostics = server.diagnostics(DOC);
+        let messages: Vec<&str> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "Genesis has 2 chapters, there is no chapter 9",
+                "John 3 has 2 verses, there is no verse 7",
+            ]
+        );
+        assert_eq!(
+            diagnostics[0].range,
+            Range::new(Position::new(2, 9), Position::new(2, 20))
+        );
+    }
+
+    #[test]
+    fn hovers_with_verse_text() {
+        let server = LanguageServer::new(sample_bible());
+        let hover = server.hover(DOC, Position::new(1, 8)).unwrap();
+        let HoverContents::Markup(content) = hover.contents else {
+            panic!("expected markdown");
+        };
+        assert!(content.value.starts_with("**Psalms 23:1**"));
+        assert!(content.value.contains("The LORD is my shepherd"));
+        assert_eq!(
+            hover.range,
+            Some(Range::new(Position::new(1, 5), Position::new(1, 15)))
+        );
+        assert!(server.hover(DOC, Position::new(0, 3)).is_none());
+    }
+
+    #[test]
+    fn completes_books_chapters_and_verses() {
+        let server = LanguageServer::new(sample_bible());
+        let labels = |text: &str| -> Vec<String> {
+            let end = position_at(text, text.len());
+            server
+                .completions(text, end)
+                .into_iter()
+                .map(|c| c.label)
+                .collect()
+        };
+
+        assert_eq!(labels("Read Psa"), vec!["Psalms"]);
+        assert!(labels("Read 1 Co").contains(&"1 Corinthians".to_string()));
+        assert_eq!(labels("Read Gen "), vec!["1", "2"]);
+        assert_eq!(labels("Read Ps 23:"), vec!["1", "2"]);
+        assert!(labels("Read Exodus 3:").is_empty());
+    }
+
+    #[test]
+    fn inserts_quoted_text_and_publishes_diagnostics() {
+        let mut server = LanguageServer::new(sample_bible());
+        let uri: Uri = "file:///notes.md".parse().unwrap();
+        let opened = server
+            .handle_notification(Notification::new(
+                "textDocument/didOpen".to_string(),
+                json!({"textDocument": {"uri": "file:///notes.md", "languageId": "markdown", "version": 1, "text": DOC}}),
+            ))
+            .unwrap();
+        assert_eq!(opened.method, "textDocument/publishDiagnostics");
+        assert_eq!(opened.params["diagnostics"].as_array().unwrap().len(), 2);
+
+        let actions = server.code_actions(
+            &uri,
+            DOC,
+            Range::new(Position::new(1, 22), Position::new(1, 22)),
+        );
+        assert_eq!(actions.len(), 1);
+        let CodeActionOrCommand::CodeAction(action) = &actions[0] else {
+            panic!("expected a code action");
+        };
+        assert_eq!(action.title, "Insert text of Genesis 1:3");
+        let edits = &action.edit.as_ref().unwrap().changes.as_ref().unwrap()[&uri];
+        assert_eq!(edits[0].range.start, Position::new(1, 27));
+        assert!(edits[0].new_text.starts_with(" \"And God said"));
+    }
+
+    #[test]
+    fn converts_utf16_positions() {
+        let text = "é👍 John 3:1\nnext";
+        let offset = text.find("John").unwrap();
+        assert_eq!(position_at(text, offset), Position::new(0, 4));
+        assert_eq!(offset_at(text, Position::new(0, 4)), offset);
+        assert_eq!(offset_at(text, Position::new(1, 2)), text.len() - 2);
+    }
+}
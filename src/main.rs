@@ -1,472 +1,647 @@
-use bincode::{Decode, Encode};
-use serde::{Deserialize, Serialize};
+#[cfg(feature = "anki")]
+use parse_bible::anki::{DeckOptions, build_apkg, deck_tsv};
+use parse_bible::compression::train_dictionary;
+use parse_bible::epub::{EpubMetadata, write_epub};
+use parse_bible::ics::plan_calendar;
+use parse_bible::latex::{latex_document, latex_passage};
+use parse_bible::linkify::DEFAULT_URL_TEMPLATE;
+#[cfg(feature = "cli")]
+use parse_bible::lsp::run_lsp;
+use parse_bible::obsidian::write_vault;
+use parse_bible::pandoc::{FilterOptions, run_filter};
+use parse_bible::plan::{PlanFormat, PlanOptions, Selection, build_plan, format_plan};
+use parse_bible::random::{Passage, RandomOptions, RandomPool, SplitMix64, verse_of_the_day};
+#[cfg(feature = "cli")]
+use parse_bible::reader::{default_state_path, run_reader};
+#[cfg(feature = "cli")]
+use parse_bible::repl::run_repl;
+use parse_bible::rpc::run_rpc;
+#[cfg(feature = "cli")]
+use parse_bible::server::{ServerOptions, serve};
+use parse_bible::site::{SiteOptions, write_site};
+use parse_bible::tree::{read_text_tree, write_text_tree};
+use parse_bible::{
+    Bible, Compression, Date, LinkFormat, VerseRef, WriteOptions, linkify, parse_gutenberg,
+    read_bible_from_bin, write_bible_to_archive, write_bible_to_bin, write_bible_to_bin_with,
+    write_bible_to_json_with, write_static_module,
+};
 use std::fs::File;
-use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::io::{BufRead, BufReader, Write};
 
-#[derive(Encode, Decode, Serialize, Deserialize, Clone)]
-struct Verse {
-    number: String,
-    text: String,
+#[derive(Default)]
+struct Args {
+    options: WriteOptions,
+    emit_rust: Option<String>, // Path of the generated static Rust module
 }
 
-#[derive(Encode, Decode, Serialize, Deserialize, Clone)]
-struct Chapter {
-    number: String,
-    verses: Vec<Verse>,
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args, Box<dyn std::error::Error>> {
+    let mut parsed = Args::default();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--compress" => {
+                let value = args.next().ok_or("--compress needs a value")?;
+                parsed.options.compression = value.parse()?;
+            }
+            "--level" => {
+                let value = args.next().ok_or("--level needs a value")?;
+                parsed.options.level = Some(value.parse()?);
+            }
+            "--emit-rust" => {
+                parsed.emit_rust = Some(args.next().ok_or("--emit-rust needs a path")?);
+            }
+            _ => return Err(format!("unknown argument '{}'", arg).into()),
+        }
+    }
+    parsed.options.check_level()?;
+    Ok(parsed)
 }
 
-#[derive(Encode, Decode, Serialize, Deserialize, Clone)]
-struct Book {
-    name: String,
-    chapters: Vec<Chapter>,
-}
+// parse-bible serve [--bin bible.bin] [--addr 127.0.0.1:8080] [--cors-origin ORIGIN]... [--threads N]
+#[cfg(feature = "cli")]
+fn run_serve(mut args: impl Iterator<Item = String>) -> Result<(), Box<dyn std::error::Error>> {
+    let mut bin_path = "bible.bin".to_string();
+    let mut options = ServerOptions::default();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--bin" => bin_path = args.next().ok_or("--bin needs a path")?,
+            "--addr" => options.addr = args.next().ok_or("--addr needs a value")?,
+            "--cors-origin" => options
+                .cors_origins
+                .push(args.next().ok_or("--cors-origin needs a value")?),
+            "--threads" => {
+                let value = args.next().ok_or("--threads needs a value")?;
+                options.threads = value.parse()?;
+            }
+            _ => return Err(format!("unknown argument '{}'", arg).into()),
+        }
+    }
 
-#[derive(Encode, Decode, Serialize, Deserialize)]
-struct Bible {
-    ot_contents: Vec<String>, // Old Testament table of contents
-    ot: Vec<Book>,            // Old Testament
-    nt_contents: Vec<String>, // New Testament table of contents
-    nt: Vec<Book>,            // New Testament
+    let bible = read_bible_from_bin(&bin_path)?;
+    serve(bible, options)
 }
 
-fn is_book_line(line: &str) -> Option<(String, bool)> {
-    let line = line.trim();
-
-    // Skip "Otherwise Called:" lines and similar
-    if line.contains("Otherwise Called") || line.is_empty() {
-        return None;
-    }
-
-    // Check exact matches first (for lines that are complete book titles)
-    if line == "The First Book of Samuel" {
-        return Some(("1 Samuel".to_string(), true));
-    }
-    if line == "The Second Book of Samuel" {
-        return Some(("2 Samuel".to_string(), true));
-    }
-
-    // Check for simple one-word book names that appear alone on a line
-    // These are the minor prophets in the OT
-    match line {
-        "Hosea" => return Some(("Hosea".to_string(), true)),
-        "Joel" => return Some(("Joel".to_string(), true)),
-        "Amos" => return Some(("Amos".to_string(), true)),
-        "Obadiah" => return Some(("Obadiah".to_string(), true)),
-        "Jonah" => return Some(("Jonah".to_string(), true)),
-        "Micah" => return Some(("Micah".to_string(), true)),
-        "Nahum" => return Some(("Nahum".to_string(), true)),
-        "Habakkuk" => return Some(("Habakkuk".to_string(), true)),
-        "Zephaniah" => return Some(("Zephaniah".to_string(), true)),
-        "Haggai" => return Some(("Haggai".to_string(), true)),
-        "Zechariah" => return Some(("Zechariah".to_string(), true)),
-        "Malachi" => return Some(("Malachi".to_string(), true)),
-        "Ezra" => return Some(("Ezra".to_string(), true)),
-        "Ecclesiastes" => return Some(("Ecclesiastes".to_string(), true)),
-        _ => {} // Continue to check prefixes
-    }
-
-    // Old Testament books - checking in order with flexible matching
-    // Only match if the line STARTS with the prefix (not just contains it)
-    let ot_books = [
-        ("The First Book of Moses:", "Genesis"),
-        ("The Second Book of Moses:", "Exodus"),
-        ("The Third Book of Moses:", "Leviticus"),
-        ("The Fourth Book of Moses:", "Numbers"),
-        ("The Fifth Book of Moses:", "Deuteronomy"),
-        ("The Book of Joshua", "Joshua"),
-        ("The Book of Judges", "Judges"),
-        ("The Book of Ruth", "Ruth"),
-        ("The First Book of the Chronicles", "1 Chronicles"),
-        ("The Second Book of the Chronicles", "2 Chronicles"),
-        ("The Book of Nehemiah", "Nehemiah"),
-        ("The Book of Esther", "Esther"),
-        ("The Book of Job", "Job"),
-        ("The Book of Psalms", "Psalms"),
-        ("The Proverbs", "Proverbs"),
-        ("The Song of Solomon", "Song of Solomon"),
-        ("The Book of the Prophet Isaiah", "Isaiah"),
-        ("The Book of the Prophet Jeremiah", "Jeremiah"),
-        ("The Lamentations of Jeremiah", "Lamentations"),
-        ("The Book of the Prophet Ezekiel", "Ezekiel"),
-        ("The Book of Daniel", "Daniel"),
-    ];
-
-    for (prefix, canonical) in &ot_books {
-        if line.starts_with(prefix) {
-            return Some((canonical.to_string(), true));
-        }
-    }
-
-    // Check Kings books AFTER all others to avoid false matches
-    if line.starts_with("The First Book of the Kings") {
-        return Some(("1 Kings".to_string(), true));
-    }
-    if line.starts_with("The Second Book of the Kings") {
-        return Some(("2 Kings".to_string(), true));
-    }
-
-    // New Testament books
-    let nt_books = [
-        ("The Gospel According to Saint Matthew", "Matthew"),
-        ("The Gospel According to Saint Mark", "Mark"),
-        ("The Gospel According to Saint Luke", "Luke"),
-        ("The Gospel According to Saint John", "John"),
-        ("The Acts of the Apostles", "Acts"),
-        ("The Epistle of Paul the Apostle to the Romans", "Romans"),
-        (
-            "The First Epistle of Paul the Apostle to the Corinthians",
-            "1 Corinthians",
-        ),
-        (
-            "The Second Epistle of Paul the Apostle to the Corinthians",
-            "2 Corinthians",
-        ),
-        (
-            "The Epistle of Paul the Apostle to the Galatians",
-            "Galatians",
-        ),
-        (
-            "The Epistle of Paul the Apostle to the Ephesians",
-            "Ephesians",
-        ),
-        (
-            "The Epistle of Paul the Apostle to the Philippians",
-            "Philippians",
-        ),
-        (
-            "The Epistle of Paul the Apostle to the Colossians",
-            "Colossians",
-        ),
-        (
-            "The First Epistle of Paul the Apostle to the Thessalonians",
-            "1 Thessalonians",
-        ),
-        (
-            "The Second Epistle of Paul the Apostle to the Thessalonians",
-            "2 Thessalonians",
-        ),
-        (
-            "The First Epistle of Paul the Apostle to Timothy",
-            "1 Timothy",
-        ),
-        (
-            "The Second Epistle of Paul the Apostle to Timothy",
-            "2 Timothy",
-        ),
-        ("The Epistle of Paul the Apostle to Titus", "Titus"),
-        ("The Epistle of Paul the Apostle to Philemon", "Philemon"),
-        ("The Epistle of Paul the Apostle to the Hebrews", "Hebrews"),
-        ("The General Epistle of James", "James"),
-        ("The First Epistle General of Peter", "1 Peter"),
-        ("The Second General Epistle of Peter", "2 Peter"),
-        ("The First Epistle General of John", "1 John"),
-        ("The Second Epistle General of John", "2 John"),
-        ("The Third Epistle General of John", "3 John"),
-        ("The General Epistle of Jude", "Jude"),
-        ("The Revelation of Saint John the Divine", "Revelation"),
-    ];
-
-    for (prefix, canonical) in &nt_books {
-        if line.starts_with(prefix) {
-            return Some((canonical.to_string(), false));
-        }
-    }
-
-    None
+// Loads the Bible for subcommands whose only option is [--bin bible.bin]
+fn load_bin_arg(
+    mut args: impl Iterator<Item = String>,
+) -> Result<Bible, Box<dyn std::error::Error>> {
+    let mut bin_path = "bible.bin".to_string();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--bin" => bin_path = args.next().ok_or("--bin needs a path")?,
+            _ => return Err(format!("unknown argument '{}'", arg).into()),
+        }
+    }
+    read_bible_from_bin(&bin_path)
 }
 
-fn parse_gutenberg(txt: &str) -> Bible {
-    let mut bible = Bible {
-        ot_contents: Vec::new(),
-        nt_contents: Vec::new(),
-        ot: Vec::new(),
-        nt: Vec::new(),
-    };
-
-    let mut current_book: Option<Book> = None;
-    let mut is_ot = true;
-    let mut current_chapter: Option<Chapter> = None;
-    let mut current_verse: Option<Verse> = None;
-
-    let mut in_bible = false;
-    let mut in_content = false; // Skip table of contents
-    let mut in_toc = false; // Track if we're in the table of contents
-    let mut toc_is_ot = true; // Track which testament's TOC we're in
-    let mut toc_complete = false; // Track when we've finished collecting TOC
-    let mut found_books = std::collections::HashSet::new();
-    let mut last_line_was_book = false;
-
-    for line in txt.lines() {
-        let line = line.trim();
-        if line.is_empty() {
-            continue;
+// parse-bible pandoc-filter [--bin bible.bin] [--footnote] [FORMAT]
+// Pandoc passes the output format when running filters; it is not needed.
+// Use as `pandoc -t json doc.md | parse-bible pandoc-filter | pandoc -f json`
+// or from a wrapper script given to `pandoc --filter`.
+fn run_pandoc_filter(
+    mut args: impl Iterator<Item = String>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut bin_path = "bible.bin".to_string();
+    let mut options = FilterOptions::default();
+    let mut format_seen = false;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--bin" => bin_path = args.next().ok_or("--bin needs a path")?,
+            "--footnote" => options.footnote = true,
+            _ if !format_seen && !arg.starts_with('-') => format_seen = true,
+            _ => return Err(format!("unknown argument '{}'", arg).into()),
         }
+    }
 
-        // Skip header until the start
-        if !in_bible {
-            if line.contains("*** START OF THE PROJECT GUTENBERG") {
-                in_bible = true;
-            }
-            continue;
-        }
+    let bible = read_bible_from_bin(&bin_path)?;
+    run_filter(
+        &bible,
+        std::io::stdin().lock(),
+        std::io::stdout().lock(),
+        &options,
+    )
+}
 
-        // Detect start of table of contents
-        if !in_toc && !in_content && line.contains("The Old Testament") {
-            in_toc = true;
-            toc_is_ot = true;
-            continue;
+// parse-bible linkify [--format html|markdown|osis] [--url TEMPLATE]
+// Copies stdin to stdout a line at a time with references turned into links
+fn run_linkify(mut args: impl Iterator<Item = String>) -> Result<(), Box<dyn std::error::Error>> {
+    let mut format = LinkFormat::default();
+    let mut url_template = DEFAULT_URL_TEMPLATE.to_string();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => format = args.next().ok_or("--format needs a value")?.parse()?,
+            "--url" => url_template = args.next().ok_or("--url needs a template")?,
+            _ => return Err(format!("unknown argument '{}'", arg).into()),
         }
+    }
 
-        if in_toc && line.contains("The New Testament") {
-            toc_is_ot = false;
-            continue;
+    let mut input = std::io::stdin().lock();
+    let mut output = std::io::stdout().lock();
+    let mut line = String::new();
+    while input.read_line(&mut line)? > 0 {
+        output.write_all(linkify(&line, format, &url_template).as_bytes())?;
+        output.flush()?;
+        line.clear();
+    }
+    Ok(())
+}
+
+// parse-bible read [--bin bible.bin] [--state reader.json]
+#[cfg(feature = "cli")]
+fn run_read(mut args: impl Iterator<Item = String>) -> Result<(), Box<dyn std::error::Error>> {
+    let mut bin_path = "bible.bin".to_string();
+    let mut state_path = default_state_path();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--bin" => bin_path = args.next().ok_or("--bin needs a path")?,
+            "--state" => state_path = args.next().ok_or("--state needs a path")?.into(),
+            _ => return Err(format!("unknown argument '{}'", arg).into()),
         }
+    }
 
-        // Collect table of contents entries while in TOC
-        if in_toc && !toc_complete {
-            if let Some((book_name, _)) = is_book_line(line) {
-                if toc_is_ot {
-                    bible.ot_contents.push(book_name);
-                } else {
-                    bible.nt_contents.push(book_name);
-                }
+    let bible = read_bible_from_bin(&bin_path)?;
+    run_reader(&bible, &state_path)
+}
 
-                // Check if we've collected all books (39 OT + 27 NT)
-                if bible.ot_contents.len() == 39 && bible.nt_contents.len() == 27 {
-                    toc_complete = true;
-                    eprintln!(
-                        "TOC complete: {} OT and {} NT entries",
-                        bible.ot_contents.len(),
-                        bible.nt_contents.len()
-                    );
-                }
+// parse-bible site [--bin bible.bin] [--out site] [--title TITLE] [--template page.html]
+fn run_site(mut args: impl Iterator<Item = String>) -> Result<(), Box<dyn std::error::Error>> {
+    let mut bin_path = "bible.bin".to_string();
+    let mut out = "site".to_string();
+    let mut options = SiteOptions::default();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--bin" => bin_path = args.next().ok_or("--bin needs a path")?,
+            "--out" => out = args.next().ok_or("--out needs a directory")?,
+            "--title" => options.title = args.next().ok_or("--title needs a value")?,
+            "--template" => {
+                let path = args.next().ok_or("--template needs a path")?;
+                options.template = std::fs::read_to_string(path)?;
             }
-            continue;
+            _ => return Err(format!("unknown argument '{}'", arg).into()),
         }
+    }
 
-        // After TOC is complete, look for the content section marker
-        if toc_complete && !in_content {
-            if line.contains("The Old Testament") {
-                in_content = true;
-                in_toc = false;
-                eprintln!("Content section starts");
-                continue;
-            }
-            continue;
-        }
+    let bible = read_bible_from_bin(&bin_path)?;
+    write_site(&bible, out.as_ref(), &options)?;
+    eprintln!("Wrote static site to {}", out);
+    Ok(())
+}
 
-        // Now we're in content - process normally
-        if !in_content {
-            continue;
+// parse-bible epub [--bin bible.bin] [--source pg10.txt] [--out kjv.epub]
+// Metadata comes from the header of the Gutenberg source when it is there
+fn run_epub(mut args: impl Iterator<Item = String>) -> Result<(), Box<dyn std::error::Error>> {
+    let mut bin_path = "bible.bin".to_string();
+    let mut source = "pg10.txt".to_string();
+    let mut out = "kjv.epub".to_string();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--bin" => bin_path = args.next().ok_or("--bin needs a path")?,
+            "--source" => source = args.next().ok_or("--source needs a path")?,
+            "--out" => out = args.next().ok_or("--out needs a path")?,
+            _ => return Err(format!("unknown argument '{}'", arg).into()),
         }
+    }
 
-        // Check for book line first
-        if let Some((book_name, is_old_testament)) = is_book_line(line) {
-            // Handle Kings/Samuel edge case
-            if last_line_was_book && (book_name == "1 Kings" || book_name == "2 Kings") {
-                eprintln!("Skipping {} as it's part of Samuel header", book_name);
-                continue;
-            }
-
-            if (book_name == "1 Kings" || book_name == "2 Kings")
-                && current_book
-                    .as_ref()
-                    .is_some_and(|b| b.name.contains("Samuel"))
-                && current_verse.is_none()
-            {
-                eprintln!(
-                    "Preventing switch from {} to {} (no verses yet)",
-                    current_book.as_ref().unwrap().name,
-                    book_name
-                );
-                continue;
-            }
-
-            if !found_books.contains(&book_name) {
-                eprintln!("Found new book: '{}' from line: '{}'", book_name, line);
-                found_books.insert(book_name.clone());
-            } else {
-                eprintln!("Re-encountered book: '{}' from line: '{}'", book_name, line);
-            }
-
-            // Save previous verse if exists
-            if let Some(verse) = current_verse.take()
-                && let Some(chapter) = current_chapter.as_mut()
-            {
-                chapter.verses.push(verse);
-            }
+    let metadata = match std::fs::read_to_string(&source) {
+        Ok(text) => EpubMetadata::from_gutenberg(&text),
+        Err(e) => {
+            eprintln!("Using default metadata, could not read {}: {}", source, e);
+            EpubMetadata::default()
+        }
+    };
+    let bible = read_bible_from_bin(&bin_path)?;
+    write_epub(&bible, &metadata, &out)?;
+    eprintln!("Wrote {}", out);
+    Ok(())
+}
 
-            // Save previous chapter if exists
-            if let Some(chapter) = current_chapter.take()
-                && let Some(book) = current_book.as_mut()
-            {
-                book.chapters.push(chapter);
+// parse-bible latex [--bin bible.bin] [--out kjv.tex] [--passage] [REFERENCE]
+// Writes to stdout without --out. --passage leaves out the preamble so the
+// output can be \input into another document.
+fn run_latex(mut args: impl Iterator<Item = String>) -> Result<(), Box<dyn std::error::Error>> {
+    let mut bin_path = "bible.bin".to_string();
+    let mut out = None;
+    let mut passage_only = false;
+    let mut reference = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--bin" => bin_path = args.next().ok_or("--bin needs a path")?,
+            "--out" => out = Some(args.next().ok_or("--out needs a path")?),
+            "--passage" => passage_only = true,
+            _ if reference.is_none() && !arg.starts_with('-') => {
+                reference = Some(VerseRef::parse(&arg)?)
             }
+            _ => return Err(format!("unknown argument '{}'", arg).into()),
+        }
+    }
 
-            // Save previous book if exists
-            if let Some(book) = current_book.take() {
-                let testament = if is_ot { &mut bible.ot } else { &mut bible.nt };
-                testament.push(book);
-            }
+    let bible = read_bible_from_bin(&bin_path)?;
+    let tex = if passage_only {
+        latex_passage(&bible, &reference.ok_or("--passage needs a reference")?)?
+    } else {
+        latex_document(&bible, reference.as_ref())?
+    };
+    match out {
+        Some(path) => std::fs::write(path, tex)?,
+        None => std::io::stdout().lock().write_all(tex.as_bytes())?,
+    }
+    Ok(())
+}
 
-            current_book = Some(Book {
-                name: book_name,
-                chapters: Vec::new(),
-            });
-            is_ot = is_old_testament;
-            last_line_was_book = true;
-            continue;
+// parse-bible obsidian [--bin bible.bin] [--out vault]
+fn run_obsidian(mut args: impl Iterator<Item = String>) -> Result<(), Box<dyn std::error::Error>> {
+    let mut bin_path = "bible.bin".to_string();
+    let mut out = "vault".to_string();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--bin" => bin_path = args.next().ok_or("--bin needs a path")?,
+            "--out" => out = args.next().ok_or("--out needs a directory")?,
+            _ => return Err(format!("unknown argument '{}'", arg).into()),
         }
+    }
 
-        last_line_was_book = false;
-
-        // Look for verse references anywhere in the line
-        let words: Vec<&str> = line.split_whitespace().collect();
+    let bible = read_bible_from_bin(&bin_path)?;
+    write_vault(&bible, out.as_ref())?;
+    eprintln!("Wrote Obsidian vault to {}", out);
+    Ok(())
+}
 
-        // First, find all verse references in this line
-        let mut verse_positions: Vec<(usize, String, String)> = Vec::new();
-        for (i, word) in words.iter().enumerate() {
-            if let Some((ch, v)) = word.split_once(':')
-                && ch.parse::<u32>().is_ok()
-                && v.parse::<u32>().is_ok()
-            {
-                verse_positions.push((i, ch.to_string(), v.to_string()));
-            }
+// parse-bible text-tree [--bin bible.bin] [--out kjv-text]
+fn run_text_tree(mut args: impl Iterator<Item = String>) -> Result<(), Box<dyn std::error::Error>> {
+    let mut bin_path = "bible.bin".to_string();
+    let mut out = "kjv-text".to_string();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--bin" => bin_path = args.next().ok_or("--bin needs a path")?,
+            "--out" => out = args.next().ok_or("--out needs a directory")?,
+            _ => return Err(format!("unknown argument '{}'", arg).into()),
         }
+    }
 
-        if !verse_positions.is_empty() {
-            // Process each verse reference found
-            for (idx, (word_pos, ch, v)) in verse_positions.iter().enumerate() {
-                // Before processing this verse, handle the previous verse
-                if let Some(mut verse) = current_verse.take() {
-                    // If this is the first verse ref on this line and there's text before it
-                    if idx == 0 && *word_pos > 0 {
-                        // Text before the first verse ref belongs to the previous verse
-                        if !verse.text.is_empty() {
-                            verse.text.push(' ');
-                        }
-                        verse.text.push_str(&words[..*word_pos].join(" "));
-                    }
-
-                    // Debug output for Matthew 14
-                    if current_chapter.as_ref().is_some_and(|c| c.number == "14")
-                        && current_book.as_ref().is_some_and(|b| b.name == "Matthew")
-                    {
-                        eprintln!("Saving verse {}: '{}'", verse.number, verse.text);
-                    }
+    let bible = read_bible_from_bin(&bin_path)?;
+    write_text_tree(&bible, out.as_ref())?;
+    eprintln!("Wrote text tree to {}", out);
+    Ok(())
+}
 
-                    if let Some(chapter) = current_chapter.as_mut() {
-                        chapter.verses.push(verse);
-                    }
-                }
+// parse-bible import-text-tree [--dir kjv-text] [--out bible.bin]
+fn run_import_text_tree(
+    mut args: impl Iterator<Item = String>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut dir = "kjv-text".to_string();
+    let mut out = "bible.bin".to_string();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--dir" => dir = args.next().ok_or("--dir needs a directory")?,
+            "--out" => out = args.next().ok_or("--out needs a path")?,
+            _ => return Err(format!("unknown argument '{}'", arg).into()),
+        }
+    }
 
-                // Check if we need a new chapter
-                if current_chapter.as_ref().is_none_or(|c| c.number != *ch) {
-                    // Save the previous chapter if exists
-                    if let Some(chapter) = current_chapter.take()
-                        && let Some(book) = current_book.as_mut()
-                    {
-                        book.chapters.push(chapter);
-                    }
-                    current_chapter = Some(Chapter {
-                        number: ch.clone(),
-                        verses: Vec::new(),
-                    });
-                }
+    let bible = read_text_tree(dir.as_ref())?;
+    write_bible_to_bin(&bible, &out)?;
+    eprintln!("Read {} and saved to {}", dir, out);
+    Ok(())
+}
 
-                // Determine the text for this verse
-                // It's from after this verse ref until the next verse ref (or end of line)
-                let text_start = word_pos + 1;
-                let text_end = if idx + 1 < verse_positions.len() {
-                    verse_positions[idx + 1].0
-                } else {
-                    words.len()
-                };
-
-                let verse_text = if text_start < text_end {
-                    words[text_start..text_end].join(" ")
-                } else {
-                    String::new()
-                };
-
-                // Debug output for Matthew 14
-                if ch == "14" && current_book.as_ref().is_some_and(|b| b.name == "Matthew") {
-                    eprintln!(
-                        "Creating verse {} with text: '{}' (positions {} to {})",
-                        v, verse_text, text_start, text_end
-                    );
+// parse-bible anki [--bin bible.bin] [--out deck.apkg] [--deck NAME] [--cards ref,text,cloze]
+//                  [--cloze-words N] [--list refs.txt] [REFERENCE]...
+// Writes an .apkg package, or Anki's tab separated import format for any
+// other extension. --list reads one reference per line.
+#[cfg(feature = "anki")]
+fn run_anki(mut args: impl Iterator<Item = String>) -> Result<(), Box<dyn std::error::Error>> {
+    let mut bin_path = "bible.bin".to_string();
+    let mut out = "kjv.apkg".to_string();
+    let mut options = DeckOptions::default();
+    let mut references = Vec::new();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--bin" => bin_path = args.next().ok_or("--bin needs a path")?,
+            "--out" => out = args.next().ok_or("--out needs a path")?,
+            "--deck" => options.name = args.next().ok_or("--deck needs a name")?,
+            "--cards" => {
+                let cards = args.next().ok_or("--cards needs a value")?;
+                let cards: Vec<&str> = cards.split(',').collect();
+                if let Some(unknown) = cards.iter().find(|c| !["ref", "text", "cloze"].contains(c))
+                {
+                    return Err(format!(
+                        "unknown card type '{}' (expected ref, text or cloze)",
+                        unknown
+                    )
+                    .into());
                 }
-
-                current_verse = Some(Verse {
-                    number: v.clone(),
-                    text: verse_text,
-                });
+                options.reference_to_text = cards.contains(&"ref");
+                options.text_to_reference = cards.contains(&"text");
+                options.cloze = cards.contains(&"cloze");
+            }
+            "--cloze-words" => {
+                let value = args.next().ok_or("--cloze-words needs a value")?;
+                options.cloze_words = value.parse()?;
             }
-        } else {
-            // No verse reference found, this is continuation text for current verse
-            if let Some(verse) = current_verse.as_mut() {
-                if !verse.text.is_empty() {
-                    verse.text.push(' ');
+            "--list" => {
+                let path = args.next().ok_or("--list needs a path")?;
+                for line in std::fs::read_to_string(path)?.lines() {
+                    if !line.trim().is_empty() {
+                        references.push(VerseRef::parse(line)?);
+                    }
                 }
-                verse.text.push_str(line);
             }
+            _ if !arg.starts_with('-') => references.push(VerseRef::parse(&arg)?),
+            _ => return Err(format!("unknown argument '{}'", arg).into()),
         }
     }
+    if references.is_empty() {
+        return Err("no references given".into());
+    }
 
-    // Save the last verse
-    if let Some(verse) = current_verse.take()
-        && let Some(chapter) = current_chapter.as_mut()
-    {
-        chapter.verses.push(verse);
+    let bible = read_bible_from_bin(&bin_path)?;
+    if out.ends_with(".apkg") {
+        std::fs::write(&out, build_apkg(&bible, &references, &options)?)?;
+    } else {
+        std::fs::write(&out, deck_tsv(&bible, &references, &options)?)?;
     }
+    eprintln!("Wrote {} passages to {}", references.len(), out);
+    Ok(())
+}
 
-    // Save the last chapter
-    if let Some(chapter) = current_chapter.take()
-        && let Some(book) = current_book.as_mut()
-    {
-        book.chapters.push(chapter);
+// Handles the reading-plan flags shared by `plan` and `ics`:
+//   [--plan bible|ot|nt|BOOK,BOOK...] [--days 365] [--by words|verses]
+//   [--parallel] [--psalms-proverbs]
+// Returns false when `arg` is not one of them.
+fn plan_arg(
+    arg: &str,
+    args: &mut impl Iterator<Item = String>,
+    options: &mut PlanOptions,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    match arg {
+        "--plan" => options.selection = args.next().ok_or("--plan needs a value")?.parse()?,
+        "--days" => options.days = args.next().ok_or("--days needs a value")?.parse()?,
+        "--by" => options.balance = args.next().ok_or("--by needs a value")?.parse()?,
+        "--parallel" => options.parallel = true,
+        "--psalms-proverbs" => options.psalms_proverbs = true,
+        _ => return Ok(false),
     }
+    Ok(true)
+}
 
-    // Save the last book
-    if let Some(book) = current_book.take() {
-        let testament = if is_ot { &mut bible.ot } else { &mut bible.nt };
-        testament.push(book);
+fn default_plan_options() -> PlanOptions {
+    PlanOptions {
+        days: 365,
+        ..PlanOptions::default()
     }
+}
 
-    eprintln!(
-        "\nFinal book counts - OT: {}, NT: {}",
-        bible.ot.len(),
-        bible.nt.len()
-    );
-    eprintln!(
-        "OT books: {:?}",
-        bible.ot.iter().map(|b| &b.name).collect::<Vec<_>>()
-    );
+// parse-bible plan [--bin bible.bin] [PLAN FLAGS] [--start YYYY-MM-DD]
+//                  [--format markdown|json|csv] [--out plan.md]
+// Writes to stdout without --out. Days are only dated when --start is given.
+fn run_plan(mut args: impl Iterator<Item = String>) -> Result<(), Box<dyn std::error::Error>> {
+    let mut bin_path = "bible.bin".to_string();
+    let mut options = default_plan_options();
+    let mut start = None;
+    let mut format = PlanFormat::default();
+    let mut out = None;
+    while let Some(arg) = args.next() {
+        if plan_arg(&arg, &mut args, &mut options)? {
+            continue;
+        }
+        match arg.as_str() {
+            "--bin" => bin_path = args.next().ok_or("--bin needs a path")?,
+            "--start" => start = Some(args.next().ok_or("--start needs a date")?.parse()?),
+            "--format" => format = args.next().ok_or("--format needs a value")?.parse()?,
+            "--out" => out = Some(args.next().ok_or("--out needs a path")?),
+            _ => return Err(format!("unknown argument '{}'", arg).into()),
+        }
+    }
 
-    bible
+    let bible = read_bible_from_bin(&bin_path)?;
+    let plan = build_plan(&bible, &options)?;
+    let text = format_plan(&plan, format, start);
+    match out {
+        Some(path) => {
+            std::fs::write(&path, text)?;
+            eprintln!("Wrote {} days to {}", plan.days, path);
+        }
+        None => print!("{}", text),
+    }
+    Ok(())
 }
 
-fn write_bible_to_bin(bible: &Bible, path: &str) -> Result<(), Box<dyn std::error::Error>> {
-    let f = File::create(path)?;
-    let mut writer = BufWriter::new(f);
+// parse-bible ics [--bin bible.bin] [PLAN FLAGS] [--start YYYY-MM-DD] [--name NAME]
+//                 [--out plan.ics]
+// Takes the same plan flags as `plan`. The plan starts today unless --start
+// is given.
+fn run_ics(mut args: impl Iterator<Item = String>) -> Result<(), Box<dyn std::error::Error>> {
+    let mut bin_path = "bible.bin".to_string();
+    let mut options = default_plan_options();
+    let mut start = Date::today();
+    let mut name = None;
+    let mut out = "plan.ics".to_string();
+    while let Some(arg) = args.next() {
+        if plan_arg(&arg, &mut args, &mut options)? {
+            continue;
+        }
+        match arg.as_str() {
+            "--bin" => bin_path = args.next().ok_or("--bin needs a path")?,
+            "--start" => start = args.next().ok_or("--start needs a date")?.parse()?,
+            "--name" => name = Some(args.next().ok_or("--name needs a value")?),
+            "--out" => out = args.next().ok_or("--out needs a path")?,
+            _ => return Err(format!("unknown argument '{}'", arg).into()),
+        }
+    }
+
+    let bible = read_bible_from_bin(&bin_path)?;
+    let plan = build_plan(&bible, &options)?;
+    let name = name.unwrap_or_else(|| {
+        let selection = match &options.selection {
+            Selection::Bible => "Bible".to_string(),
+            Selection::OldTestament => "Old Testament".to_string(),
+            Selection::NewTestament => "New Testament".to_string(),
+            Selection::Books(books) => books.join(", "),
+        };
+        format!("{} in {} days", selection, options.days)
+    });
+    std::fs::write(
+        &out,
+        plan_calendar(&plan, &name, start, std::time::SystemTime::now()),
+    )?;
+    eprintln!("Wrote {} days from {} to {}", plan.days, start, out);
+    Ok(())
+}
 
-    let config = bincode::config::standard();
-    bincode::encode_into_std_write(bible, &mut writer, config)?;
+fn print_passage(passage: &Passage) {
+    println!("{}", passage.reference());
+    if let [verse] = passage.verses {
+        println!("{}", verse.text);
+    } else {
+        for verse in passage.verses {
+            println!("{} {}", verse.number, verse.text);
+        }
+    }
+}
 
-    // CRITICAL: Flush the buffer before dropping
-    writer.flush()?;
+// parse-bible votd [--bin bible.bin] [--date YYYY-MM-DD] [--pool curated|bible]
+// Everyone gets the same verse on the same (UTC) date
+fn run_votd(mut args: impl Iterator<Item = String>) -> Result<(), Box<dyn std::error::Error>> {
+    let mut bin_path = "bible.bin".to_string();
+    let mut date = Date::today();
+    let mut pool = Default::default();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--bin" => bin_path = args.next().ok_or("--bin needs a path")?,
+            "--date" => date = args.next().ok_or("--date needs a date")?.parse()?,
+            "--pool" => pool = args.next().ok_or("--pool needs a value")?.parse()?,
+            _ => return Err(format!("unknown argument '{}'", arg).into()),
+        }
+    }
 
+    let bible = read_bible_from_bin(&bin_path)?;
+    let passage = verse_of_the_day(&bible, date, pool).ok_or("no verses to choose from")?;
+    print_passage(&passage);
     Ok(())
 }
 
-fn write_bible_to_json(bible: &Bible, path: &str) -> Result<(), Box<dyn std::error::Error>> {
-    let f = File::create(path)?;
-    let writer = BufWriter::new(f);
-    serde_json::to_writer_pretty(writer, bible)?;
+// parse-bible random [--bin bible.bin] [--seed N] [--from bible|ot|nt|BOOK,BOOK...]
+//                    [--verses 1] [--min-words N] [--count 1]
+// Without --seed one is taken from the clock and printed to stderr so the
+// selection can be repeated
+fn run_random(mut args: impl Iterator<Item = String>) -> Result<(), Box<dyn std::error::Error>> {
+    let mut bin_path = "bible.bin".to_string();
+    let mut seed = None;
+    let mut options = RandomOptions::default();
+    let mut count = 1;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--bin" => bin_path = args.next().ok_or("--bin needs a path")?,
+            "--seed" => seed = Some(args.next().ok_or("--seed needs a value")?.parse()?),
+            "--from" => options.selection = args.next().ok_or("--from needs a value")?.parse()?,
+            "--verses" => options.verses = args.next().ok_or("--verses needs a value")?.parse()?,
+            "--min-words" => {
+                options.min_words = args.next().ok_or("--min-words needs a value")?.parse()?
+            }
+            "--count" => count = args.next().ok_or("--count needs a value")?.parse()?,
+            _ => return Err(format!("unknown argument '{}'", arg).into()),
+        }
+    }
+
+    let seed = seed.unwrap_or_else(|| {
+        let seed = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as u64);
+        eprintln!("Seed {}", seed);
+        seed
+    });
+    let bible = read_bible_from_bin(&bin_path)?;
+    let pool = RandomPool::new(&bible, &options);
+    if pool.is_empty() {
+        return Err("no passages match the options".into());
+    }
+    let mut rng = SplitMix64::new(seed);
+    for i in 0..count {
+        let passage = pool.pick(&mut rng).expect("the pool is not empty");
+        if i > 0 {
+            println!();
+        }
+        print_passage(&passage);
+    }
     Ok(())
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut args = std::env::args().skip(1).peekable();
+    match args.peek().map(String::as_str) {
+        #[cfg(feature = "cli")]
+        Some("serve") => {
+            args.next();
+            return run_serve(args);
+        }
+        // JSON-RPC 2.0 with one message per line
+        Some("rpc") => {
+            args.next();
+            let bible = load_bin_arg(args)?;
+            return run_rpc(bible, std::io::stdin().lock(), std::io::stdout().lock());
+        }
+        Some("pandoc-filter") => {
+            args.next();
+            return run_pandoc_filter(args);
+        }
+        Some("linkify") => {
+            args.next();
+            return run_linkify(args);
+        }
+        #[cfg(feature = "cli")]
+        Some("read") => {
+            args.next();
+            return run_read(args);
+        }
+        Some("site") => {
+            args.next();
+            return run_site(args);
+        }
+        Some("epub") => {
+            args.next();
+            return run_epub(args);
+        }
+        Some("latex") => {
+            args.next();
+            return run_latex(args);
+        }
+        Some("obsidian") => {
+            args.next();
+            return run_obsidian(args);
+        }
+        Some("text-tree") => {
+            args.next();
+            return run_text_tree(args);
+        }
+        Some("import-text-tree") => {
+            args.next();
+            return run_import_text_tree(args);
+        }
+        #[cfg(feature = "anki")]
+        Some("anki") => {
+            args.next();
+            return run_anki(args);
+        }
+        #[cfg(not(feature = "anki"))]
+        Some("anki") => {
+            return Err("the anki subcommand needs the `anki` feature".into());
+        }
+        Some("plan") => {
+            args.next();
+            return run_plan(args);
+        }
+        Some("ics") => {
+            args.next();
+            return run_ics(args);
+        }
+        Some("votd") => {
+            args.next();
+            return run_votd(args);
+        }
+        Some("random") => {
+            args.next();
+            return run_random(args);
+        }
+        #[cfg(feature = "cli")]
+        Some("repl") => {
+            args.next();
+            return run_repl(&load_bin_arg(args)?);
+        }
+        #[cfg(feature = "cli")]
+        Some("lsp") => {
+            args.next();
+            return run_lsp(load_bin_arg(args)?);
+        }
+        #[cfg(not(feature = "cli"))]
+        Some(command @ ("serve" | "read" | "repl" | "lsp")) => {
+            return Err(format!("the {} subcommand needs the `cli` feature", command).into());
+        }
+        _ => {}
+    }
+
+    let args = parse_args(args)?;
+    let mut options = args.options;
+
     // Read the full Gutenberg KJV text file
     let file = File::open("pg10.txt")?;
     let reader = BufReader::new(file);
@@ -502,492 +677,25 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         println!("  {}. {}", i + 1, book.name);
     }
 
-    write_bible_to_bin(&bible, "bible.bin")?;
-    write_bible_to_json(&bible, "bible.json")?;
-
-    println!("\nParsed KJV and saved to bible.bin successfully!");
-    Ok(())
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    // Helper function to find a book by name
-    fn find_book<'a>(testament: &'a [Book], name: &str) -> &'a Book {
-        testament
-            .iter()
-            .find(|b| b.name == name)
-            .unwrap_or_else(|| panic!("Book {} not found", name))
-    }
-
-    // Helper function to find a chapter by number
-    fn find_chapter<'a>(book: &'a Book, number: &str) -> &'a Chapter {
-        book.chapters
-            .iter()
-            .find(|c| c.number == number)
-            .unwrap_or_else(|| panic!("Chapter {} not found in {}", number, book.name))
-    }
-
-    // Helper function to find a verse by number
-    fn find_verse<'a>(chapter: &'a Chapter, number: &str) -> &'a Verse {
-        chapter
-            .verses
-            .iter()
-            .find(|v| v.number == number)
-            .unwrap_or_else(|| panic!("Verse {} not found", number))
-    }
-
-    // if not exists we write the binary
-    // yet always return bible
-    fn get_bible() -> Bible {
-        let root_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
-        let bin_path = format!("{}/bible.bin", root_dir);
-        if std::path::Path::new(&bin_path).exists() {
-            // Read existing binary
-            let data = std::fs::read(&bin_path).expect("Failed to read bible.bin");
-            let config = bincode::config::standard();
-            let (bible, _): (Bible, usize) =
-                bincode::decode_from_slice(&data, config).expect("Failed to decode bible.bin");
-            bible
-        } else {
-            // Parse and write new binary
-            let file = File::open("pg10.txt").expect("Failed to open pg10.txt");
-            let reader = BufReader::new(file);
-            let txt = reader
-                .lines()
-                .collect::<Result<Vec<_>, _>>()
-                .unwrap()
-                .join("\n");
-            let bible = parse_gutenberg(&txt);
-            write_bible_to_bin(&bible, &bin_path).expect("Failed to write bible.bin");
-            bible
-        }
-    }
-
-    #[test]
-    fn test_parse_gutenberg() {
-        let file = File::open("pg10.txt").expect("Failed to open pg10.txt");
-        let reader = BufReader::new(file);
-        let txt = reader
-            .lines()
-            .collect::<Result<Vec<_>, _>>()
-            .unwrap()
-            .join("\n");
-        let bible = parse_gutenberg(&txt);
-        assert_eq!(bible.ot.len(), 39, "Should have 39 OT books");
-        assert_eq!(bible.nt.len(), 27, "Should have 27 NT books");
-
-        let root_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
-        let bin_path = format!("{}/bible.bin", root_dir);
-        write_bible_to_bin(&bible, &bin_path).expect("Failed to write bible.bin");
-    }
-
-    #[test]
-    fn verify_bible_binary() -> Result<(), Box<dyn std::error::Error>> {
-        let bible = get_bible();
-
-        // Verify some known verses
-        let genesis = find_book(&bible.ot, "Genesis");
-        let gen_ch1 = find_chapter(genesis, "1");
-        let gen_1_1 = find_verse(gen_ch1, "1");
-        assert_eq!(
-            gen_1_1.text,
-            "In the beginning God created the heaven and the earth."
-        );
-
-        // Verify chapter counts
-        assert_eq!(
-            genesis.chapters.len(),
-            50,
-            "Genesis should have 50 chapters"
-        );
-
-        let matthew = find_book(&bible.nt, "Matthew");
-        assert_eq!(
-            matthew.chapters.len(),
-            28,
-            "Matthew should have 28 chapters"
-        );
-
-        // Matthew 3:1
-        let matt_ch3 = find_chapter(matthew, "3");
-        let matt_3_1 = find_verse(matt_ch3, "1");
-        assert_eq!(
-            matt_3_1.text,
-            "In those days came John the Baptist, preaching in the wilderness of Judaea,"
-        );
-
-        Ok(())
-    }
-
-    #[test]
-    fn verify_matthew_14_verses() -> Result<(), Box<dyn std::error::Error>> {
-        let bible = get_bible();
-        let matthew = find_book(&bible.nt, "Matthew");
-        let matt_ch14 = find_chapter(matthew, "14");
-
-        // Verify verse 1
-        let matt_14_1 = find_verse(matt_ch14, "1");
-        assert_eq!(
-            matt_14_1.text,
-            "At that time Herod the tetrarch heard of the fame of Jesus,"
-        );
-
-        // Verify verse 2 exists and has correct content
-        let matt_14_2 = find_verse(matt_ch14, "2");
-        assert_eq!(
-            matt_14_2.text,
-            "And said unto his servants, This is John the Baptist; he is risen from the dead; and therefore mighty works do shew forth themselves in him."
-        );
-
-        // Verify verse 3
-        let matt_14_3 = find_verse(matt_ch14, "3");
-        assert_eq!(
-            matt_14_3.text,
-            "For Herod had laid hold on John, and bound him, and put him in prison for Herodias’ sake, his brother Philip’s wife."
-        );
-
-        // Make sure we have all verses in Matthew 14 (should have 36 verses)
-        assert_eq!(
-            matt_ch14.verses.len(),
-            36,
-            "Matthew 14 should have 36 verses"
-        );
-
-        // Verify all verse numbers are sequential
-        for i in 1..=36 {
-            let verse = find_verse(matt_ch14, &i.to_string());
-            assert_eq!(
-                verse.number,
-                i.to_string(),
-                "Verse {} should have number {}",
-                i,
-                i
-            );
-            assert!(!verse.text.is_empty(), "Verse {} should not be empty", i);
-        }
-
-        Ok(())
-    }
-
-    #[test]
-    fn verify_all_chapter_counts() -> Result<(), Box<dyn std::error::Error>> {
-        let bible = get_bible();
-
-        // Old Testament chapter counts
-        let ot_chapters = [
-            ("Genesis", 50),
-            ("Exodus", 40),
-            ("Leviticus", 27),
-            ("Numbers", 36),
-            ("Deuteronomy", 34),
-            ("Joshua", 24),
-            ("Judges", 21),
-            ("Ruth", 4),
-            ("1 Samuel", 31),
-            ("2 Samuel", 24),
-            ("1 Kings", 22),
-            ("2 Kings", 25),
-            ("1 Chronicles", 29),
-            ("2 Chronicles", 36),
-            ("Ezra", 10),
-            ("Nehemiah", 13),
-            ("Esther", 10),
-            ("Job", 42),
-            ("Psalms", 150),
-            ("Proverbs", 31),
-            ("Ecclesiastes", 12),
-            ("Song of Solomon", 8),
-            ("Isaiah", 66),
-            ("Jeremiah", 52),
-            ("Lamentations", 5),
-            ("Ezekiel", 48),
-            ("Daniel", 12),
-            ("Hosea", 14),
-            ("Joel", 3),
-            ("Amos", 9),
-            ("Obadiah", 1),
-            ("Jonah", 4),
-            ("Micah", 7),
-            ("Nahum", 3),
-            ("Habakkuk", 3),
-            ("Zephaniah", 3),
-            ("Haggai", 2),
-            ("Zechariah", 14),
-            ("Malachi", 4),
-        ];
-
-        for (book_name, expected_chapters) in &ot_chapters {
-            let book = find_book(&bible.ot, book_name);
-            let actual_chapters = book.chapters.len();
-            if actual_chapters != *expected_chapters {
-                eprintln!(
-                    "\n{} has {} chapters (expected {}). Chapters: {:?}",
-                    book_name,
-                    actual_chapters,
-                    expected_chapters,
-                    book.chapters.iter().map(|c| &c.number).collect::<Vec<_>>()
-                );
-            }
-            assert_eq!(
-                actual_chapters, *expected_chapters,
-                "{} should have {} chapters",
-                book_name, expected_chapters
-            );
-        }
-
-        // New Testament chapter counts
-        let nt_chapters = [
-            ("Matthew", 28),
-            ("Mark", 16),
-            ("Luke", 24),
-            ("John", 21),
-            ("Acts", 28),
-            ("Romans", 16),
-            ("1 Corinthians", 16),
-            ("2 Corinthians", 13),
-            ("Galatians", 6),
-            ("Ephesians", 6),
-            ("Philippians", 4),
-            ("Colossians", 4),
-            ("1 Thessalonians", 5),
-            ("2 Thessalonians", 3),
-            ("1 Timothy", 6),
-            ("2 Timothy", 4),
-            ("Titus", 3),
-            ("Philemon", 1),
-            ("Hebrews", 13),
-            ("James", 5),
-            ("1 Peter", 5),
-            ("2 Peter", 3),
-            ("1 John", 5),
-            ("2 John", 1),
-            ("3 John", 1),
-            ("Jude", 1),
-            ("Revelation", 22),
-        ];
-
-        for (book_name, expected_chapters) in &nt_chapters {
-            let book = find_book(&bible.nt, book_name);
-            assert_eq!(
-                book.chapters.len(),
-                *expected_chapters,
-                "{} should have {} chapters",
-                book_name,
-                expected_chapters
-            );
-        }
-
-        Ok(())
-    }
-
-    #[test]
-    fn verify_specific_verse_counts() -> Result<(), Box<dyn std::error::Error>> {
-        let bible = get_bible();
-
-        // Genesis 1 should have 31 verses
-        let genesis = find_book(&bible.ot, "Genesis");
-        let gen_ch1 = find_chapter(genesis, "1");
-        assert_eq!(gen_ch1.verses.len(), 31, "Genesis 1 should have 31 verses");
-
-        // Psalm 119 should have 176 verses (longest chapter)
-        let psalms = find_book(&bible.ot, "Psalms");
-        let ps_119 = find_chapter(psalms, "119");
-        assert_eq!(ps_119.verses.len(), 176, "Psalm 119 should have 176 verses");
-
-        // John 3 should have 36 verses
-        let john = find_book(&bible.nt, "John");
-        let john_ch3 = find_chapter(john, "3");
-        assert_eq!(john_ch3.verses.len(), 36, "John 3 should have 36 verses");
-
-        // Romans 8 should have 39 verses
-        let romans = find_book(&bible.nt, "Romans");
-        let rom_ch8 = find_chapter(romans, "8");
-        assert_eq!(rom_ch8.verses.len(), 39, "Romans 8 should have 39 verses");
-
-        // Matthew 5 (Sermon on the Mount) should have 48 verses
-        let matthew = find_book(&bible.nt, "Matthew");
-        let matt_ch5 = find_chapter(matthew, "5");
-        assert_eq!(matt_ch5.verses.len(), 48, "Matthew 5 should have 48 verses");
-
-        // Revelation 22 (last chapter) should have 21 verses
-        let revelation = find_book(&bible.nt, "Revelation");
-        let rev_ch22 = find_chapter(revelation, "22");
-        assert_eq!(
-            rev_ch22.verses.len(),
-            21,
-            "Revelation 22 should have 21 verses"
-        );
-
-        Ok(())
-    }
-
-    #[test]
-    fn verify_famous_verses() -> Result<(), Box<dyn std::error::Error>> {
-        let bible = get_bible();
-
-        // John 3:16 - Most famous verse
-        let john = find_book(&bible.nt, "John");
-        let john_ch3 = find_chapter(john, "3");
-        let john_3_16 = find_verse(john_ch3, "16");
-        assert_eq!(
-            john_3_16.text,
-            "For God so loved the world, that he gave his only begotten Son, that whosoever believeth in him should not perish, but have everlasting life."
-        );
-
-        // Genesis 1:1 - Opening verse
-        let genesis = find_book(&bible.ot, "Genesis");
-        let gen_ch1 = find_chapter(genesis, "1");
-        let gen_1_1 = find_verse(gen_ch1, "1");
-        assert_eq!(
-            gen_1_1.text,
-            "In the beginning God created the heaven and the earth."
-        );
-
-        // Psalm 23:1 - The Lord is my shepherd
-        let psalms = find_book(&bible.ot, "Psalms");
-        let ps_23 = find_chapter(psalms, "23");
-        let ps_23_1 = find_verse(ps_23, "1");
-        assert_eq!(ps_23_1.text, "The LORD is my shepherd; I shall not want.");
-
-        // Romans 8:28
-        let romans = find_book(&bible.nt, "Romans");
-        let rom_ch8 = find_chapter(romans, "8");
-        let rom_8_28 = find_verse(rom_ch8, "28");
-        assert_eq!(
-            rom_8_28.text,
-            "And we know that all things work together for good to them that love God, to them who are the called according to his purpose."
-        );
-
-        // Jeremiah 29:11
-        let jeremiah = find_book(&bible.ot, "Jeremiah");
-        let jer_ch29 = find_chapter(jeremiah, "29");
-        let jer_29_11 = find_verse(jer_ch29, "11");
-        assert_eq!(
-            jer_29_11.text,
-            "For I know the thoughts that I think toward you, saith the LORD, thoughts of peace, and not of evil, to give you an expected end."
-        );
-
-        // Philippians 4:13
-        let philippians = find_book(&bible.nt, "Philippians");
-        let phil_ch4 = find_chapter(philippians, "4");
-        let phil_4_13 = find_verse(phil_ch4, "13");
-        assert_eq!(
-            phil_4_13.text,
-            "I can do all things through Christ which strengtheneth me."
-        );
-
-        // Proverbs 3:5-6 (test verse 5)
-        let proverbs = find_book(&bible.ot, "Proverbs");
-        let prov_ch3 = find_chapter(proverbs, "3");
-        let prov_3_5 = find_verse(prov_ch3, "5");
-        assert_eq!(
-            prov_3_5.text,
-            "Trust in the LORD with all thine heart; and lean not unto thine own understanding."
-        );
-
-        // Matthew 28:19 - Great Commission
-        let matthew = find_book(&bible.nt, "Matthew");
-        let matt_ch28 = find_chapter(matthew, "28");
-        let matt_28_19 = find_verse(matt_ch28, "19");
-        assert_eq!(
-            matt_28_19.text,
-            "Go ye therefore, and teach all nations, baptizing them in the name of the Father, and of the Son, and of the Holy Ghost:"
-        );
-
-        // Isaiah 40:31
-        let isaiah = find_book(&bible.ot, "Isaiah");
-        let isa_ch40 = find_chapter(isaiah, "40");
-        let isa_40_31 = find_verse(isa_ch40, "31");
-        assert_eq!(
-            isa_40_31.text,
-            "But they that wait upon the LORD shall renew their strength; they shall mount up with wings as eagles; they shall run, and not be weary; and they shall walk, and not faint."
-        );
-
-        // 1 Corinthians 13:4 - Love chapter
-        let cor1 = find_book(&bible.nt, "1 Corinthians");
-        let cor1_ch13 = find_chapter(cor1, "13");
-        let cor1_13_4 = find_verse(cor1_ch13, "4");
-        assert_eq!(
-            cor1_13_4.text,
-            "Charity suffereth long, and is kind; charity envieth not; charity vaunteth not itself, is not puffed up,"
-        );
-
-        Ok(())
-    }
-
-    #[test]
-    fn verify_verse_lengths() -> Result<(), Box<dyn std::error::Error>> {
-        let bible = get_bible();
-
-        // Verify that verses have reasonable lengths (not empty, not too short)
-        // John 11:35 is the shortest verse: "Jesus wept."
-        let john = find_book(&bible.nt, "John");
-        let john_ch11 = find_chapter(john, "11");
-        let john_11_35 = find_verse(john_ch11, "35");
-        assert_eq!(john_11_35.text, "Jesus wept.");
-        assert!(
-            john_11_35.text.len() < 20,
-            "Shortest verse should be very short"
-        );
-
-        // Check that no verses are empty
-        for book in bible.ot.iter() {
-            for chapter in book.chapters.iter() {
-                for verse in chapter.verses.iter() {
-                    assert!(
-                        !verse.text.is_empty(),
-                        "Empty verse found in OT {} {}:{}",
-                        book.name,
-                        chapter.number,
-                        verse.number
-                    );
-                    assert!(
-                        verse.text.len() >= 2,
-                        "Suspiciously short verse in OT {} {}:{}: '{}'",
-                        book.name,
-                        chapter.number,
-                        verse.number,
-                        verse.text
-                    );
-                }
-            }
-        }
-
-        for book in bible.nt.iter() {
-            for chapter in book.chapters.iter() {
-                for verse in chapter.verses.iter() {
-                    assert!(
-                        !verse.text.is_empty(),
-                        "Empty verse found in NT {} {}:{}",
-                        book.name,
-                        chapter.number,
-                        verse.number
-                    );
-                    assert!(
-                        verse.text.len() >= 2,
-                        "Suspiciously short verse in NT {} {}:{}: '{}'",
-                        book.name,
-                        chapter.number,
-                        verse.number,
-                        verse.text
-                    );
-                }
-            }
-        }
-
-        // Check some longer verses
-        // Esther 8:9 is one of the longest verses
-        let esther = find_book(&bible.ot, "Esther");
-        let esther_ch8 = find_chapter(esther, "8");
-        let esther_8_9 = find_verse(esther_ch8, "9");
-        assert!(
-            esther_8_9.text.len() > 300,
-            "Esther 8:9 should be a long verse (>300 chars), got {}",
-            esther_8_9.text.len()
-        );
+    // The dictionary is written once next to the outputs; clients need it to
+    // load them
+    if options.compression == Compression::ZstdDict {
+        let dictionary = train_dictionary(&bible)?;
+        std::fs::write("bible.zdict", &dictionary)?;
+        println!("\nTrained zstd dictionary saved to bible.zdict");
+        options.dictionary = Some(dictionary);
+    }
 
-        Ok(())
+    let ext = options.compression.extension();
+    let bin_path = format!("bible.bin{}", ext);
+    write_bible_to_bin_with(&bible, &bin_path, &options)?;
+    write_bible_to_json_with(&bible, &format!("bible.json{}", ext), &options)?;
+    write_bible_to_archive(&bible, "bible.kjva")?;
+    if let Some(path) = &args.emit_rust {
+        write_static_module(&bible, path)?;
+        println!("Generated static Rust module at {}", path);
     }
+
+    println!("\nParsed KJV and saved to {} successfully!", bin_path);
+    Ok(())
 }
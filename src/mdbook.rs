@@ -0,0 +1,224 @@
+use crate::model::Bible;
+use crate::reference::VerseRef;
+use serde_json::Value;
+use std::error::Error;
+use std::io::{Read, Write};
+use std::path::Path;
+
+// Delimiters around a placeholder reference, "{{kjv John 3:16}}" by default
+#[derive(Clone, Debug)]
+pub struct PlaceholderSyntax {
+    pub open: String,
+    pub close: String,
+}
+
+impl Default for PlaceholderSyntax {
+    fn default() -> Self {
+        PlaceholderSyntax {
+            open: "{{kjv".to_string(),
+            close: "}}".to_string(),
+        }
+    }
+}
+
+// Settings read from [preprocessor.kjv] in book.toml
+#[derive(Clone, Debug)]
+pub struct PreprocessorConfig {
+    pub bible: String,
+    pub syntax: PlaceholderSyntax,
+}
+
+impl PreprocessorConfig {
+    pub fn from_context(context: &Value) -> Self {
+        let table = &context["config"]["preprocessor"]["kjv"];
+        let setting = |key: &str| table[key].as_str().map(str::to_string);
+        let defaults = PlaceholderSyntax::default();
+        let bible = setting("bible").unwrap_or_else(|| "bible.bin".to_string());
+        // Relative paths are resolved against the book root, like mdBook does
+        let bible = match context["root"].as_str() {
+            Some(root) => Path::new(root).join(&bible).to_string_lossy().into_owned(),
+            None => bible,
+        };
+        PreprocessorConfig {
+            bible,
+            syntax: PlaceholderSyntax {
+                open: setting("open").unwrap_or(defaults.open),
+                close: setting("close").unwrap_or(defaults.close),
+            },
+        }
+    }
+}
+
+// The passage as a Markdown blockquote followed by its reference
+pub fn format_passage(bible: &Bible, reference: &VerseRef) -> Result<String, Box<dyn Error>> {
+    let verses = bible.lookup(reference)?;
+    let text = if reference.is_single_verse() {
+        verses[0].text.clone()
+    } else {
+        let numbered: Vec<String> = verses
+            .iter()
+            .map(|v| format!("<sup>{}</sup> {}", v.number, v.text))
+            .collect();
+        numbered.join(" ")
+    };
+    Ok(format!("> {}\n>\n> — *{}*", text, reference))
+}
+
+// Replaces every placeholder in a chapter's Markdown with its passage
+pub fn expand_placeholders(
+    bible: &Bible,
+    content: &str,
+    syntax: &PlaceholderSyntax,
+) -> Result<String, Box<dyn Error>> {
+    let mut out = String::with_capacity(content.len());
+    let mut rest = content;
+    while let Some(start) = rest.find(&syntax.open) {
+        let after_open = &rest[start + syntax.open.len()..];
+        let end = after_open.find(&syntax.close).ok_or_else(|| {
+            format!(
+                "unclosed placeholder '{}'",
+                &rest[start..].lines().next().unwrap_or("")
+            )
+        })?;
+        let reference = VerseRef::parse(&after_open[..end])?;
+        out.push_str(&rest[..start]);
+        out.push_str(&format_passage(bible, &reference)?);
+        rest = &after_open[end + syntax.close.len()..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+// Walks the chapters of the book JSON mdBook hands to preprocessors
+fn expand_items(
+    bible: &Bible,
+    items: &mut Value,
+    syntax: &PlaceholderSyntax,
+) -> Result<(), Box<dyn Error>> {
+    let Some(items) = items.as_array_mut() else {
+        return Ok(());
+    };
+    for item in items {
+        let Some(chapter) = item.get_mut("Chapter") else {
+            continue;
+        };
+        if let Some(content) = chapter["content"].as_str() {
+            let name = chapter["name"].as_str().unwrap_or_default().to_string();
+            let expanded = expand_placeholders(bible, content, syntax)
+                .map_err(|e| format!("chapter '{}': {}", name, e))?;
+            chapter["content"] = Value::String(expanded);
+        }
+        expand_items(bible, &mut chapter["sub_items"], syntax)?;
+    }
+    Ok(())
+}
+
+// Expands placeholders in a book; the Bible is loaded from the configured
+// path unless one is given
+pub fn preprocess_book(
+    context: &Value,
+    mut book: Value,
+    bible: Option<&Bible>,
+) -> Result<Value, Box<dyn Error>> {
+    let config = PreprocessorConfig::from_context(context);
+    let loaded;
+    let bible = match bible {
+        Some(bible) => bible,
+        None => {
+            loaded = crate::read_bible_from_bin(&config.bible)
+                .map_err(|e| format!("failed to load {}: {}", config.bible, e))?;
+            &loaded
+        }
+    };
+    // mdBook 0.4 calls the top level list "sections", 0.5 "items"
+    for key in ["sections", "items"] {
+        if let Some(items) = book.get_mut(key) {
+            expand_items(bible, items, &config.syntax)?;
+        }
+    }
+    Ok(book)
+}
+
+// Reads the [context, book] pair from mdBook and writes back the book
+pub fn run_preprocessor(input: impl Read, mut output: impl Write) -> Result<(), Box<dyn Error>> {
+    let (context, book): (Value, Value) = serde_json::from_reader(input)?;
+    let book = preprocess_book(&context, book, None)?;
+    serde_json::to_writer(&mut output, &book)?;
+    output.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::sample_bible;
+    use serde_json::json;
+
+    #[test]
+    fn expands_placeholders_to_blockquotes() {
+        let bible = sample_bible();
+        let syntax = PlaceholderSyntax::default();
+        let expanded =
+            expand_placeholders(&bible, "Intro\n\n{{kjv Ps 23:1}}\n\nMore", &syntax).unwrap();
+        assert_eq!(
+            expanded,
+            "Intro\n\n> The LORD is my shepherd; I shall not want.\n>\n> — *Psalms 23:1*\n\nMore"
+        );
+
+        let passage = expand_placeholders(&bible, "{{kjv Genesis 1:1-2}}", &syntax).unwrap();
+        assert!(passage.starts_with("> <sup>1</sup> In the beginning"));
+        assert!(passage.contains(" <sup>2</sup> And the earth"));
+        assert!(passage.ends_with("> — *Genesis 1:1-2*"));
+
+        let custom = PlaceholderSyntax {
+            open: "<<".to_string(),
+            close: ">>".to_string(),
+        };
+        let expanded = expand_placeholders(&bible, "<<John 3:1>>", &custom).unwrap();
+        assert!(expanded.ends_with("— *John 3:1*"));
+    }
+
+    #[test]
+    fn rejects_unresolvable_placeholders() {
+        let bible = sample_bible();
+        let syntax = PlaceholderSyntax::default();
+        let err = expand_placeholders(&bible, "{{kjv John 3:40}}", &syntax).unwrap_err();
+        assert_eq!(err.to_string(), "John 3 has 2 verses, there is no verse 40");
+        assert!(expand_placeholders(&bible, "{{kjv Hezekiah 1:1}}", &syntax).is_err());
+        assert!(expand_placeholders(&bible, "{{kjv John 3:1", &syntax).is_err());
+    }
+
+    #[test]
+    fn preprocesses_nested_chapters() {
+        let bible = sample_bible();
+        let context =
+            json!({"root": "/book", "config": {"preprocessor": {"kjv": {"bible": "kjv.bin"}}}});
+        assert_eq!(
+            PreprocessorConfig::from_context(&context).bible,
+            "/book/kjv.bin"
+        );
+
+        let book = json!({"sections": [
+            {"Chapter": {"name": "One", "content": "{{kjv Gen 1:3}}", "sub_items": [
+                {"Chapter": {"name": "Nested", "content": "{{kjv John 3:2}}", "sub_items": []}}
+            ]}},
+            "Separator",
+        ]});
+        let book = preprocess_book(&context, book, Some(&bible)).unwrap();
+        let chapter = &book["sections"][0]["Chapter"];
+        assert!(
+            chapter["content"]
+                .as_str()
+                .unwrap()
+                .contains("Let there be light")
+        );
+        let nested = chapter["sub_items"][0]["Chapter"]["content"]
+            .as_str()
+            .unwrap();
+        assert!(nested.ends_with("— *John 3:2*"));
+
+        let broken = json!({"sections": [{"Chapter": {"name": "Bad", "content": "{{kjv Gen 5:1}}", "sub_items": []}}]});
+        let err = preprocess_book(&context, broken, Some(&bible)).unwrap_err();
+        assert!(err.to_string().starts_with("chapter 'Bad': "));
+    }
+}
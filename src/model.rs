@@ -0,0 +1,144 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use bincode::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+
+#[derive(Encode, Decode, Serialize, Deserialize, Clone)]
+pub struct Verse {
+    pub number: String,
+    pub text: String,
+}
+
+#[derive(Encode, Decode, Serialize, Deserialize, Clone)]
+pub struct Chapter {
+    pub number: String,
+    pub verses: Vec<Verse>,
+}
+
+#[derive(Encode, Decode, Serialize, Deserialize, Clone)]
+pub struct Book {
+    pub name: String,
+    pub chapters: Vec<Chapter>,
+}
+
+#[derive(Encode, Decode, Serialize, Deserialize)]
+pub struct Bible {
+    pub ot_contents: Vec<String>, // Old Testament table of contents
+    pub ot: Vec<Book>,            // Old Testament
+    pub nt_contents: Vec<String>, // New Testament table of contents
+    pub nt: Vec<Book>,            // New Testament
+}
+
+impl Bible {
+    // All books in canonical order, Old Testament first
+    pub fn books(&self) -> impl Iterator<Item = &Book> {
+        self.ot.iter().chain(self.nt.iter())
+    }
+
+    pub fn book(&self, name: &str) -> Option<&Book> {
+        self.books().find(|b| b.name == name)
+    }
+
+    pub fn chapter(&self, book: &str, chapter: &str) -> Option<&Chapter> {
+        self.book(book)?.chapter(chapter)
+    }
+
+    pub fn verse(&self, book: &str, chapter: &str, verse: &str) -> Option<&Verse> {
+        self.chapter(book, chapter)?.verse(verse)
+    }
+}
+
+impl Book {
+    pub fn chapter(&self, number: &str) -> Option<&Chapter> {
+        self.chapters.iter().find(|c| c.number == number)
+    }
+}
+
+impl Chapter {
+    pub fn verse(&self, number: &str) -> Option<&Verse> {
+        self.verses.iter().find(|v| v.number == number)
+    }
+}
+
+#[cfg(test)]
+pub(crate) fn sample_bible() -> Bible {
+    // Small hand-built Bible for tests that should not depend on pg10.txt
+    fn chapter(number: &str, verses: &[&str]) -> Chapter {
+        Chapter {
+            number: number.to_string(),
+            verses: verses
+                .iter()
+                .enumerate()
+                .map(|(i, text)| Verse {
+                    number: (i + 1).to_string(),
+                    text: text.to_string(),
+                })
+                .collect(),
+        }
+    }
+
+    Bible {
+        ot_contents: vec!["Genesis".to_string(), "Psalms".to_string()],
+        ot: vec![
+            Book {
+                name: "Genesis".to_string(),
+                chapters: vec![
+                    chapter(
+                        "1",
+                        &[
+                            "In the beginning God created the heaven and the earth.",
+                            "And the earth was without form, and void; and darkness was upon the face of the deep. And the Spirit of God moved upon the face of the waters.",
+                            "And God said, Let there be light: and there was light.",
+                        ],
+                    ),
+                    chapter(
+                        "2",
+                        &[
+                            "Thus the heavens and the earth were finished, and all the host of them.",
+                        ],
+                    ),
+                ],
+            },
+            Book {
+                name: "Psalms".to_string(),
+                chapters: vec![chapter(
+                    "23",
+                    &[
+                        "The LORD is my shepherd; I shall not want.",
+                        "He maketh me to lie down in green pastures: he leadeth me beside the still waters.",
+                    ],
+                )],
+            },
+        ],
+        nt_contents: vec!["John".to_string()],
+        nt: vec![Book {
+            name: "John".to_string(),
+            chapters: vec![chapter(
+                "3",
+                &[
+                    "There was a man of the Pharisees, named Nicodemus, a ruler of the Jews:",
+                    "The same came to Jesus by night, and said unto him, Rabbi, we know that thou art a teacher come from God: for no man can do these miracles that thou doest, except God be with him.",
+                ],
+            )],
+        }],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lookup_by_book_chapter_and_verse() {
+        let bible = sample_bible();
+
+        assert_eq!(bible.books().count(), 3);
+        assert_eq!(
+            bible.verse("Psalms", "23", "1").map(|v| v.text.as_str()),
+            Some("The LORD is my shepherd; I shall not want.")
+        );
+        assert_eq!(bible.book("John").map(|b| b.chapters.len()), Some(1));
+        assert!(bible.chapter("Genesis", "3").is_none());
+        assert!(bible.verse("Exodus", "1", "1").is_none());
+    }
+}
@@ -0,0 +1,153 @@
+use crate::model::{Bible, Book, Chapter};
+use std::error::Error;
+use std::fmt::Write as _;
+use std::path::Path;
+
+// Note names double as wiki-link targets, so they have to be unique across
+// the vault: "Genesis" for the book index and "Genesis 1" for each chapter
+fn chapter_note(book: &Book, chapter: &Chapter) -> String {
+    format!("{} {}", book.name, chapter.number)
+}
+
+fn testament(bible: &Bible, book: &Book) -> &'static str {
+    if bible.ot.iter().any(|b| b.name == book.name) {
+        "Old Testament"
+    } else {
+        "New Testament"
+    }
+}
+
+fn render_book(bible: &Bible, book: &Book) -> String {
+    let mut out = format!(
+        "---\nbook: {}\ntestament: {}\nchapters: {}\n---\n\n# {}\n\n",
+        book.name,
+        testament(bible, book),
+        book.chapters.len(),
+        book.name
+    );
+    for chapter in &book.chapters {
+        writeln!(out, "- [[{}]]", chapter_note(book, chapter)).unwrap();
+    }
+    out
+}
+
+fn render_chapter(
+    bible: &Bible,
+    book: &Book,
+    chapter: &Chapter,
+    prev: Option<(&Book, &Chapter)>,
+    next: Option<(&Book, &Chapter)>,
+) -> String {
+    let mut nav = Vec::new();
+    if let Some((book, chapter)) = prev {
+        nav.push(format!("← [[{}]]", chapter_note(book, chapter)));
+    }
+    nav.push(format!("[[{}]]", book.name));
+    if let Some((book, chapter)) = next {
+        nav.push(format!("[[{}]] →", chapter_note(book, chapter)));
+    }
+    let nav = nav.join(" | ");
+
+    let mut out = format!(
+        "---\nbook: {}\nchapter: {}\ntestament: {}\n---\n\n# {}\n\n{}\n\n",
+        book.name,
+        chapter.number,
+        testament(bible, book),
+        chapter_note(book, chapter),
+        nav
+    );
+    // A paragraph per verse so each block ID covers exactly one verse,
+    // linkable as [[Genesis 1#^v1]]
+    for verse in &chapter.verses {
+        writeln!(
+            out,
+            "**{}** {} ^v{}\n",
+            verse.number, verse.text, verse.number
+        )
+        .unwrap();
+    }
+    out.push_str(&nav);
+    out.push('\n');
+    out
+}
+
+// All notes of the vault as (relative path, contents): a folder per book with
+// its index note and one note per chapter
+pub fn render_vault(bible: &Bible) -> Vec<(String, String)> {
+    let chapters: Vec<(&Book, &Chapter)> = bible
+        .books()
+        .flat_map(|book| book.chapters.iter().map(move |chapter| (book, chapter)))
+        .collect();
+
+    let mut files = Vec::new();
+    for book in bible.books() {
+        files.push((
+            format!("{}/{}.md", book.name, book.name),
+            render_book(bible, book),
+        ));
+    }
+    for (i, &(book, chapter)) in chapters.iter().enumerate() {
+        let prev = i.checked_sub(1).map(|p| chapters[p]);
+        let next = chapters.get(i + 1).copied();
+        files.push((
+            format!("{}/{}.md", book.name, chapter_note(book, chapter)),
+            render_chapter(bible, book, chapter, prev, next),
+        ));
+    }
+    files
+}
+
+pub fn write_vault(bible: &Bible, dir: &Path) -> Result<(), Box<dyn Error>> {
+    for (path, contents) in render_vault(bible) {
+        let path = dir.join(path);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, contents)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::sample_bible;
+
+    fn note<'a>(files: &'a [(String, String)], path: &str) -> &'a str {
+        &files.iter().find(|(p, _)| p == path).unwrap().1
+    }
+
+    #[test]
+    fn writes_chapter_notes_with_block_ids() {
+        let bible = sample_bible();
+        let files = render_vault(&bible);
+        assert_eq!(files.len(), 7);
+
+        let chapter = note(&files, "Genesis/Genesis 2.md");
+        assert!(chapter.starts_with(
+            "---\nbook: Genesis\nchapter: 2\ntestament: Old Testament\n---\n\n# Genesis 2\n\n\
+             ← [[Genesis 1]] | [[Genesis]] | [[Psalms 23]] →\n\n"
+        ));
+        assert!(chapter.contains(
+            "**1** Thus the heavens and the earth were finished, and all the host of them. ^v1\n"
+        ));
+        assert!(chapter.ends_with("\n\n← [[Genesis 1]] | [[Genesis]] | [[Psalms 23]] →\n"));
+
+        assert!(note(&files, "Genesis/Genesis 1.md").contains("\n[[Genesis]] | [[Genesis 2]] →\n"));
+        let john = note(&files, "John/John 3.md");
+        assert!(john.contains("testament: New Testament\n"));
+        assert!(john.contains("\n← [[Psalms 23]] | [[John]]\n"));
+        assert!(john.contains(" ^v2\n"));
+    }
+
+    #[test]
+    fn writes_book_index_notes() {
+        let bible = sample_bible();
+        let files = render_vault(&bible);
+        assert_eq!(
+            note(&files, "Genesis/Genesis.md"),
+            "---\nbook: Genesis\ntestament: Old Testament\nchapters: 2\n---\n\n# Genesis\n\n\
+             - [[Genesis 1]]\n- [[Genesis 2]]\n"
+        );
+    }
+}
@@ -0,0 +1,195 @@
+use crate::model::Bible;
+use crate::reference::VerseRef;
+use serde_json::{Value, json};
+use std::error::Error;
+use std::io::{Read, Write};
+
+// Spans and inline code carrying this class are replaced, e.g.
+// [John 3:16]{.kjv} or `Ps 23:1`{.kjv} in Markdown
+const CLASS: &str = "kjv";
+
+#[derive(Clone, Debug, Default)]
+pub struct FilterOptions {
+    // Add a footnote holding the reference after each quotation. Documents
+    // can also turn this on with `kjv-footnote: true` in their metadata.
+    pub footnote: bool,
+}
+
+fn text_inlines(text: &str) -> Vec<Value> {
+    let mut inlines = Vec::new();
+    for word in text.split_whitespace() {
+        if !inlines.is_empty() {
+            inlines.push(json!({"t": "Space"}));
+        }
+        inlines.push(json!({"t": "Str", "c": word}));
+    }
+    inlines
+}
+
+// Plain text of a list of inlines, enough for the words of a reference
+fn stringify(inlines: &Value) -> String {
+    let mut out = String::new();
+    for inline in inlines.as_array().into_iter().flatten() {
+        match inline["t"].as_str() {
+            Some("Str") => out.push_str(inline["c"].as_str().unwrap_or_default()),
+            Some("Space" | "SoftBreak" | "LineBreak") => out.push(' '),
+            _ => {}
+        }
+    }
+    out
+}
+
+struct Filter<'a> {
+    bible: &'a Bible,
+    footnote: bool,
+}
+
+impl Filter<'_> {
+    // The reference text of a marked Span or Code, None for anything else.
+    // A `ref` attribute takes precedence over the element's content.
+    fn marked_reference(element: &Value) -> Option<String> {
+        let kind = element["t"].as_str()?;
+        if kind != "Span" && kind != "Code" {
+            return None;
+        }
+        let [_, classes, attributes] = element["c"][0].as_array()?.as_slice() else {
+            return None;
+        };
+        if !classes.as_array()?.iter().any(|c| c == CLASS) {
+            return None;
+        }
+        let attribute = attributes.as_array()?.iter().find_map(|kv| {
+            (kv[0] == "ref").then(|| kv[1].as_str().unwrap_or_default().to_string())
+        });
+        Some(attribute.unwrap_or_else(|| match kind {
+            "Code" => element["c"][1].as_str().unwrap_or_default().to_string(),
+            _ => stringify(&element["c"][1]),
+        }))
+    }
+
+    // A span keeping the original attributes around the quoted text
+    fn quotation(&self, element: &Value, reference: &str) -> Result<Value, Box<dyn Error>> {
+        let reference = VerseRef::parse(reference)?;
+        let verses = self.bible.lookup(&reference)?;
+        let texts: Vec<&str> = verses.iter().map(|v| v.text.as_str()).collect();
+
+        let mut inlines = vec![json!({
+            "t": "Quoted",
+            "c": [{"t": "DoubleQuote"}, text_inlines(&texts.join(" "))],
+        })];
+        if self.footnote {
+            let note = text_inlines(&format!("{}.", reference));
+            inlines.push(json!({"t": "Note", "c": [{"t": "Para", "c": note}]}));
+        }
+        Ok(json!({"t": "Span", "c": [element["c"][0].clone(), inlines]}))
+    }
+
+    fn walk(&self, value: &mut Value) -> Result<(), Box<dyn Error>> {
+        if let Some(reference) = Self::marked_reference(value) {
+            *value = self.quotation(value, &reference)?;
+            return Ok(());
+        }
+        match value {
+            Value::Array(items) => items.iter_mut().try_for_each(|v| self.walk(v)),
+            Value::Object(map) => map.values_mut().try_for_each(|v| self.walk(v)),
+            _ => Ok(()),
+        }
+    }
+}
+
+// Replaces marked references in a pandoc JSON document
+pub fn filter_document(
+    bible: &Bible,
+    mut document: Value,
+    options: &FilterOptions,
+) -> Result<Value, Box<dyn Error>> {
+    let meta = &document["meta"]["kjv-footnote"];
+    let footnote = options.footnote || (meta["t"] == "MetaBool" && meta["c"] == true);
+    let filter = Filter { bible, footnote };
+    filter.walk(&mut document["blocks"])?;
+    Ok(document)
+}
+
+// Reads pandoc JSON from input and writes the filtered document to output
+pub fn run_filter(
+    bible: &Bible,
+    input: impl Read,
+    mut output: impl Write,
+    options: &FilterOptions,
+) -> Result<(), Box<dyn Error>> {
+    let document: Value = serde_json::from_reader(input)?;
+    let document = filter_document(bible, document, options)?;
+    serde_json::to_writer(&mut output, &document)?;
+    output.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::sample_bible;
+
+    // As produced by `pandoc -t json` for
+    // "See [Ps 23:1]{.kjv} and `John 3:2`{.kjv #nico}."
+    fn document(meta: Value) -> Value {
+        json!({
+            "pandoc-api-version": [1, 23, 1],
+            "meta": meta,
+            "blocks": [{"t": "Para", "c": [
+                {"t": "Str", "c": "See"},
+                {"t": "Space"},
+                {"t": "Span", "c": [["", ["kjv"], []], [
+                    {"t": "Str", "c": "Ps"}, {"t": "Space"}, {"t": "Str", "c": "23:1"}
+                ]]},
+                {"t": "Space"},
+                {"t": "Str", "c": "and"},
+                {"t": "Space"},
+                {"t": "Code", "c": [["nico", ["kjv"], []], "John 3:2"]},
+                {"t": "Str", "c": "."}
+            ]}]
+        })
+    }
+
+    #[test]
+    fn substitutes_marked_spans_and_code() {
+        let bible = sample_bible();
+        let filtered =
+            filter_document(&bible, document(json!({})), &FilterOptions::default()).unwrap();
+        let para = &filtered["blocks"][0]["c"];
+
+        let span = &para[2];
+        assert_eq!(span["t"], "Span");
+        let quoted = &span["c"][1][0];
+        assert_eq!(quoted["t"], "Quoted");
+        assert_eq!(
+            stringify(&quoted["c"][1]),
+            "The LORD is my shepherd; I shall not want."
+        );
+        assert_eq!(span["c"][1].as_array().unwrap().len(), 1);
+
+        let code = &para[6];
+        assert_eq!(code["t"], "Span");
+        assert_eq!(code["c"][0][0], "nico");
+        assert!(stringify(&code["c"][1][0]["c"][1]).starts_with("The same came to Jesus"));
+        assert_eq!(filtered["pandoc-api-version"], json!([1, 23, 1]));
+    }
+
+    #[test]
+    fn adds_footnotes_when_asked() {
+        let bible = sample_bible();
+        let meta = json!({"kjv-footnote": {"t": "MetaBool", "c": true}});
+        let filtered = filter_document(&bible, document(meta), &FilterOptions::default()).unwrap();
+        let note = &filtered["blocks"][0]["c"][2]["c"][1][1];
+        assert_eq!(note["t"], "Note");
+        assert_eq!(stringify(&note["c"][0]["c"]), "Psalms 23:1.");
+    }
+
+    #[test]
+    fn fails_on_unresolvable_references() {
+        let bible = sample_bible();
+        let mut doc = document(json!({}));
+        doc["blocks"][0]["c"][6]["c"][1] = json!("John 3:99");
+        let err = filter_document(&bible, doc, &FilterOptions::default()).unwrap_err();
+        assert_eq!(err.to_string(), "John 3 has 2 verses, there is no verse 99");
+    }
+}
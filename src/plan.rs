@@ -0,0 +1,501 @@
+use crate::date::Date;
+use crate::model::{Bible, Book, Chapter};
+use crate::reference::resolve_book;
+use crate::search::words;
+use serde_json::json;
+use std::fmt::Write as _;
+use std::str::FromStr;
+
+// The part of the Bible a plan reads through
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum Selection {
+    #[default]
+    Bible,
+    OldTestament,
+    NewTestament,
+    Books(Vec<&'static str>),
+}
+
+impl FromStr for Selection {
+    type Err = String;
+
+    // "bible", "ot", "nt" or a comma separated list of books ("Gen,Exod,Matt")
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bible" => Ok(Selection::Bible),
+            "ot" => Ok(Selection::OldTestament),
+            "nt" => Ok(Selection::NewTestament),
+            _ => s
+                .split(',')
+                .map(|name| {
+                    resolve_book(name).ok_or_else(|| format!("unknown book '{}'", name.trim()))
+                })
+                .collect::<Result<_, _>>()
+                .map(Selection::Books),
+        }
+    }
+}
+
+impl Selection {
+    // The selected books in canonical order, with the testament they are in
+    pub(crate) fn books<'a>(&self, bible: &'a Bible) -> Vec<(&'a Book, bool)> {
+        let ot = bible.ot.iter().map(|book| (book, true));
+        let nt = bible.nt.iter().map(|book| (book, false));
+        ot.chain(nt)
+            .filter(|(book, is_ot)| match self {
+                Selection::Bible => true,
+                Selection::OldTestament => *is_ot,
+                Selection::NewTestament => !*is_ot,
+                Selection::Books(names) => names.contains(&book.name.as_str()),
+            })
+            .collect()
+    }
+}
+
+// What daily portions are balanced by
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Balance {
+    #[default]
+    Words,
+    Verses,
+}
+
+impl FromStr for Balance {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "words" => Ok(Balance::Words),
+            "verses" => Ok(Balance::Verses),
+            _ => Err(format!(
+                "unknown balance '{}' (expected words or verses)",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct PlanOptions {
+    pub selection: Selection,
+    pub days: usize,
+    pub balance: Balance,
+    // Read the Old and New Testament parts of the selection side by side
+    pub parallel: bool,
+    // A Psalm and a chapter of Proverbs every day, starting over at the end
+    // of each book. Both are left out of the other tracks.
+    pub psalms_proverbs: bool,
+}
+
+// One day's portion of a track: chapters as references such as
+// "Genesis 1-3" or "Malachi 4; Matthew 1", and their size
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Reading {
+    pub references: Vec<String>,
+    pub verses: usize,
+    pub words: usize,
+}
+
+// A named sequence of readings, one per day
+#[derive(Clone, Debug)]
+pub struct Track {
+    pub name: String,
+    pub readings: Vec<Reading>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Plan {
+    pub days: usize,
+    pub tracks: Vec<Track>,
+}
+
+impl Plan {
+    // The readings of every track for a day, counting from 0. Panics when
+    // `day` is not below `days`, as do day_summary and day_words.
+    pub fn day(&self, day: usize) -> impl Iterator<Item = &Reading> {
+        assert!(
+            day < self.days,
+            "day {} is past the end of a {} day plan",
+            day,
+            self.days
+        );
+        self.tracks.iter().map(move |track| &track.readings[day])
+    }
+
+    // All references of a day, "Genesis 1-2; Psalms 1"
+    pub fn day_summary(&self, day: usize) -> String {
+        let references: Vec<&str> = self
+            .day(day)
+            .flat_map(|r| r.references.iter().map(String::as_str))
+            .collect();
+        references.join("; ")
+    }
+
+    pub fn day_words(&self, day: usize) -> usize {
+        self.day(day).map(|r| r.words).sum()
+    }
+}
+
+struct Portion<'a> {
+    book: &'a Book,
+    chapter: &'a Chapter,
+    verses: usize,
+    words: usize,
+}
+
+fn portion<'a>(book: &'a Book, chapter: &'a Chapter) -> Portion<'a> {
+    Portion {
+        book,
+        chapter,
+        verses: chapter.verses.len(),
+        words: chapter.verses.iter().map(|v| words(&v.text).count()).sum(),
+    }
+}
+
+// Joins runs of chapters from the same book into one reference
+fn reading(chapters: &[Portion]) -> Reading {
+    let mut references = Vec::new();
+    let mut i = 0;
+    while i < chapters.len() {
+        let first = &chapters[i];
+        while i + 1 < chapters.len() && chapters[i + 1].book.name == first.book.name {
+            i += 1;
+        }
+        let last = &chapters[i];
+        if first.chapter.number == last.chapter.number {
+            references.push(format!("{} {}", first.book.name, first.chapter.number));
+        } else {
+            references.push(format!(
+                "{} {}-{}",
+                first.book.name, first.chapter.number, last.chapter.number
+            ));
+        }
+        i += 1;
+    }
+    Reading {
+        references,
+        verses: chapters.iter().map(|c| c.verses).sum(),
+        words: chapters.iter().map(|c| c.words).sum(),
+    }
+}
+
+// Splits chapters into `days` consecutive readings of roughly equal size.
+// Chapters are never split, and every day gets at least one.
+fn balanced(chapters: &[Portion], days: usize, balance: Balance) -> Vec<Reading> {
+    let size = |c: &Portion| match balance {
+        Balance::Words => c.words,
+        Balance::Verses => c.verses,
+    };
+    let mut readings = Vec::with_capacity(days);
+    let mut remaining: usize = chapters.iter().map(size).sum();
+    let mut next = 0;
+    for day in 0..days {
+        let days_left = days - day;
+        let target = remaining as f64 / days_left as f64;
+        let start = next;
+        let mut taken = 0;
+        // Take chapters while that gets closer to the day's share, leaving at
+        // least one chapter for each of the following days
+        while next < chapters.len() - (days_left - 1) {
+            let with_next = taken + size(&chapters[next]);
+            if next > start && with_next as f64 - target > target - taken as f64 {
+                break;
+            }
+            taken = with_next;
+            next += 1;
+        }
+        if day == days - 1 {
+            next = chapters.len();
+        }
+        readings.push(reading(&chapters[start..next]));
+        remaining -= chapters[start..next].iter().map(size).sum::<usize>();
+    }
+    readings
+}
+
+// Divides the selection into daily readings
+pub fn build_plan(bible: &Bible, options: &PlanOptions) -> Result<Plan, String> {
+    let days = options.days;
+    if days == 0 {
+        return Err("a plan needs at least one day".to_string());
+    }
+    let daily = ["Psalms", "Proverbs"];
+    let books: Vec<(&Book, bool)> = options
+        .selection
+        .books(bible)
+        .into_iter()
+        .filter(|(book, _)| !options.psalms_proverbs || !daily.contains(&book.name.as_str()))
+        .collect();
+
+    let mut parts: Vec<(&str, Vec<&Book>)> = Vec::new();
+    if options.parallel {
+        for (name, ot) in [("Old Testament", true), ("New Testament", false)] {
+            let part: Vec<&Book> = books.iter().filter(|b| b.1 == ot).map(|b| b.0).collect();
+            if !part.is_empty() {
+                parts.push((name, part));
+            }
+        }
+    } else if !books.is_empty() {
+        parts.push(("Reading", books.iter().map(|b| b.0).collect()));
+    }
+
+    let mut tracks = Vec::new();
+    for (name, part) in parts {
+        let chapters: Vec<Portion> = part
+            .iter()
+            .flat_map(|book| book.chapters.iter().map(move |c| portion(book, c)))
+            .collect();
+        if days > chapters.len() {
+            return Err(format!(
+                "the {} track has {} chapters, too few for {} days",
+                name.to_lowercase(),
+                chapters.len(),
+                days
+            ));
+        }
+        tracks.push(Track {
+            name: name.to_string(),
+            readings: balanced(&chapters, days, options.balance),
+        });
+    }
+    if options.psalms_proverbs {
+        for name in daily {
+            let book = bible
+                .book(name)
+                .filter(|b| !b.chapters.is_empty())
+                .ok_or_else(|| format!("{} is missing from the Bible", name))?;
+            let readings = (0..days)
+                .map(|day| reading(&[portion(book, &book.chapters[day % book.chapters.len()])]))
+                .collect();
+            tracks.push(Track {
+                name: name.to_string(),
+                readings,
+            });
+        }
+    }
+    if tracks.is_empty() {
+        return Err("the selection has no chapters".to_string());
+    }
+    Ok(Plan { days, tracks })
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PlanFormat {
+    #[default]
+    Markdown,
+    Json,
+    Csv,
+}
+
+impl FromStr for PlanFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "markdown" | "md" => Ok(PlanFormat::Markdown),
+            "json" => Ok(PlanFormat::Json),
+            "csv" => Ok(PlanFormat::Csv),
+            _ => Err(format!(
+                "unknown plan format '{}' (expected markdown, json or csv)",
+                s
+            )),
+        }
+    }
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+// The schedule a day per row. Dates are included when the plan has a start.
+pub fn format_plan(plan: &Plan, format: PlanFormat, start: Option<Date>) -> String {
+    let date = |day: usize| start.map(|s| s.add_days(day as i64).to_string());
+    let mut out = String::new();
+    match format {
+        PlanFormat::Json => {
+            let days: Vec<_> = (0..plan.days)
+                .map(|day| {
+                    let readings: Vec<_> = plan
+                        .tracks
+                        .iter()
+                        .map(|track| {
+                            let reading = &track.readings[day];
+                            json!({
+                                "track": track.name,
+                                "references": reading.references,
+                                "verses": reading.verses,
+                                "words": reading.words,
+                            })
+                        })
+                        .collect();
+                    let mut entry = json!({"day": day + 1, "readings": readings});
+                    if let Some(date) = date(day) {
+                        entry["date"] = json!(date);
+                    }
+                    entry
+                })
+                .collect();
+            out = serde_json::to_string_pretty(&days).unwrap();
+            out.push('\n');
+        }
+        PlanFormat::Markdown => {
+            let mut header = vec!["Day".to_string()];
+            if start.is_some() {
+                header.push("Date".to_string());
+            }
+            header.extend(plan.tracks.iter().map(|t| t.name.clone()));
+            header.push("Words".to_string());
+            writeln!(out, "| {} |", header.join(" | ")).unwrap();
+            writeln!(out, "|{}", "---|".repeat(header.len())).unwrap();
+            for day in 0..plan.days {
+                let mut row = vec![(day + 1).to_string()];
+                row.extend(date(day));
+                row.extend(plan.day(day).map(|r| r.references.join("; ")));
+                row.push(plan.day_words(day).to_string());
+                writeln!(out, "| {} |", row.join(" | ")).unwrap();
+            }
+        }
+        PlanFormat::Csv => {
+            let mut header = vec!["day".to_string()];
+            if start.is_some() {
+                header.push("date".to_string());
+            }
+            header.extend(plan.tracks.iter().map(|t| t.name.to_lowercase()));
+            header.push("words".to_string());
+            writeln!(out, "{}", header.join(",")).unwrap();
+            for day in 0..plan.days {
+                let mut row = vec![(day + 1).to_string()];
+                row.extend(date(day));
+                row.extend(plan.day(day).map(|r| csv_field(&r.references.join("; "))));
+                row.push(plan.day_words(day).to_string());
+                writeln!(out, "{}", row.join(",")).unwrap();
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::sample_bible;
+
+    fn references(plan: &Plan) -> Vec<String> {
+        (0..plan.days).map(|day| plan.day_summary(day)).collect()
+    }
+
+    #[test]
+    fn balances_chapters_over_days() {
+        let bible = sample_bible();
+        let mut options = PlanOptions {
+            days: 2,
+            ..PlanOptions::default()
+        };
+        let plan = build_plan(&bible, &options).unwrap();
+        assert_eq!(references(&plan), ["Genesis 1-2", "Psalms 23; John 3"]);
+        let total: usize = (0..plan.days).map(|day| plan.day_words(day)).sum();
+        let all: usize = bible
+            .books()
+            .flat_map(|b| &b.chapters)
+            .flat_map(|c| &c.verses)
+            .map(|v| words(&v.text).count())
+            .sum();
+        assert_eq!(total, all);
+
+        // Genesis 1 has the most verses, so it gets a day of its own
+        options.balance = Balance::Verses;
+        options.days = 3;
+        let plan = build_plan(&bible, &options).unwrap();
+        assert_eq!(
+            references(&plan),
+            ["Genesis 1", "Genesis 2; Psalms 23", "John 3"]
+        );
+        assert_eq!(plan.day(0).next().unwrap().verses, 3);
+
+        options.selection = "Gen,John".parse().unwrap();
+        options.days = 5;
+        assert!(build_plan(&bible, &options).is_err());
+        assert!("Gen,Hezekiah".parse::<Selection>().is_err());
+    }
+
+    #[test]
+    fn runs_parallel_and_daily_tracks() {
+        let bible = sample_bible();
+        let options = PlanOptions {
+            days: 2,
+            parallel: true,
+            ..PlanOptions::default()
+        };
+        assert!(build_plan(&bible, &options).is_err());
+
+        let mut bible = bible;
+        bible.ot.push(Book {
+            name: "Proverbs".to_string(),
+            chapters: bible.ot[1].chapters.clone(),
+        });
+        let options = PlanOptions {
+            days: 1,
+            parallel: true,
+            psalms_proverbs: true,
+            ..PlanOptions::default()
+        };
+        let plan = build_plan(&bible, &options).unwrap();
+        let names: Vec<&str> = plan.tracks.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(
+            names,
+            ["Old Testament", "New Testament", "Psalms", "Proverbs"]
+        );
+        assert_eq!(
+            references(&plan),
+            ["Genesis 1-2; John 3; Psalms 23; Proverbs 23"]
+        );
+
+        let options = PlanOptions {
+            selection: Selection::Books(vec!["John"]),
+            days: 3,
+            psalms_proverbs: true,
+            ..options
+        };
+        assert!(build_plan(&bible, &options).is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "day 2 is past the end of a 2 day plan")]
+    fn rejects_days_past_the_end() {
+        let options = PlanOptions {
+            days: 2,
+            ..PlanOptions::default()
+        };
+        build_plan(&sample_bible(), &options).unwrap().day_words(2);
+    }
+
+    #[test]
+    fn formats_schedules() {
+        let bible = sample_bible();
+        let options = PlanOptions {
+            days: 2,
+            ..PlanOptions::default()
+        };
+        let plan = build_plan(&bible, &options).unwrap();
+        let start = Date::new(2025, 1, 1);
+
+        let markdown = format_plan(&plan, PlanFormat::Markdown, start);
+        assert!(markdown.starts_with(
+            "| Day | Date | Reading | Words |\n|---|---|---|---|\n| 1 | 2025-01-01 | Genesis 1-2 | "
+        ));
+        let csv = format_plan(&plan, PlanFormat::Csv, None);
+        assert!(csv.starts_with("day,reading,words\n1,Genesis 1-2,"));
+        assert!(csv.contains("\n2,Psalms 23; John 3,"));
+
+        let json: serde_json::Value =
+            serde_json::from_str(&format_plan(&plan, PlanFormat::Json, start)).unwrap();
+        assert_eq!(json[1]["date"], "2025-01-02");
+        assert_eq!(json[1]["readings"][0]["references"][1], "John 3");
+        assert_eq!(json[1]["readings"][0]["verses"], 4);
+        assert_eq!(csv_field("a, \"b\""), "\"a, \"\"b\"\"\"");
+    }
+}
@@ -0,0 +1,358 @@
+use crate::date::Date;
+use crate::model::{Bible, Chapter, Verse};
+use crate::plan::Selection;
+use crate::reference::VerseRef;
+use crate::search::words;
+use std::str::FromStr;
+
+// SplitMix64 (Steele, Lea and Flood), small and fast with a fixed output
+// for a given seed on every platform, which is all that is needed here
+#[derive(Clone, Debug)]
+pub struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> Self {
+        SplitMix64 { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // A number in 0..n (n > 0), by Lemire's multiply and shift
+    pub fn below(&mut self, n: usize) -> usize {
+        ((self.next_u64() as u128 * n as u128) >> 64) as usize
+    }
+}
+
+// Well known verses for the curated verse of the day
+pub const CURATED_VERSES: &[&str] = &[
+    "Genesis 1:1",
+    "Genesis 1:3",
+    "Numbers 6:24",
+    "Deuteronomy 31:6",
+    "Joshua 1:9",
+    "1 Samuel 16:7",
+    "Psalms 23:1",
+    "Psalms 27:1",
+    "Psalms 37:4",
+    "Psalms 46:1",
+    "Psalms 46:10",
+    "Psalms 119:105",
+    "Psalms 121:1",
+    "Psalms 139:14",
+    "Proverbs 3:5",
+    "Proverbs 3:6",
+    "Proverbs 16:3",
+    "Proverbs 22:6",
+    "Ecclesiastes 3:1",
+    "Isaiah 9:6",
+    "Isaiah 26:3",
+    "Isaiah 40:31",
+    "Isaiah 41:10",
+    "Isaiah 53:5",
+    "Jeremiah 29:11",
+    "Lamentations 3:22",
+    "Lamentations 3:23",
+    "Micah 6:8",
+    "Habakkuk 3:18",
+    "Matthew 5:9",
+    "Matthew 5:14",
+    "Matthew 6:33",
+    "Matthew 7:7",
+    "Matthew 11:28",
+    "Matthew 22:37",
+    "Matthew 28:20",
+    "Mark 10:27",
+    "Luke 1:37",
+    "Luke 6:31",
+    "John 1:1",
+    "John 3:16",
+    "John 8:32",
+    "John 11:25",
+    "John 13:34",
+    "John 14:6",
+    "John 14:27",
+    "John 16:33",
+    "Acts 1:8",
+    "Romans 5:8",
+    "Romans 8:28",
+    "Romans 12:2",
+    "Romans 15:13",
+    "1 Corinthians 10:13",
+    "1 Corinthians 13:4",
+    "1 Corinthians 13:13",
+    "2 Corinthians 5:17",
+    "2 Corinthians 12:9",
+    "Galatians 2:20",
+    "Galatians 5:22",
+    "Ephesians 2:8",
+    "Ephesians 4:32",
+    "Philippians 4:6",
+    "Philippians 4:7",
+    "Philippians 4:13",
+    "Colossians 3:23",
+    "2 Timothy 1:7",
+    "Hebrews 11:1",
+    "Hebrews 13:8",
+    "James 1:5",
+    "1 Peter 5:7",
+    "1 John 1:9",
+    "1 John 4:8",
+    "1 John 4:19",
+    "Revelation 21:4",
+];
+
+// Where the verse of the day is drawn from
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Pool {
+    #[default]
+    Curated,
+    Bible,
+}
+
+impl FromStr for Pool {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "curated" => Ok(Pool::Curated),
+            "bible" => Ok(Pool::Bible),
+            _ => Err(format!("unknown pool '{}' (expected curated or bible)", s)),
+        }
+    }
+}
+
+// Consecutive verses of one chapter
+#[derive(Clone, Copy)]
+pub struct Passage<'a> {
+    pub book: &'a str,
+    pub chapter: &'a str,
+    pub verses: &'a [Verse],
+}
+
+impl Passage<'_> {
+    // "John 3:16" or "John 3:16-18"
+    pub fn reference(&self) -> String {
+        let first = &self.verses[0].number;
+        let last = &self.verses[self.verses.len() - 1].number;
+        if first == last {
+            format!("{} {}:{}", self.book, self.chapter, first)
+        } else {
+            format!("{} {}:{}-{}", self.book, self.chapter, first, last)
+        }
+    }
+
+    pub fn words(&self) -> usize {
+        self.verses.iter().map(|v| words(&v.text).count()).sum()
+    }
+}
+
+fn find_verse<'a>(bible: &'a Bible, reference: &str) -> Option<Passage<'a>> {
+    let reference = VerseRef::parse(reference).ok()?;
+    let book = bible.book(reference.book)?;
+    let chapter = book.chapter(&reference.chapter.to_string())?;
+    let verse = reference.verse?.to_string();
+    let index = chapter.verses.iter().position(|v| v.number == verse)?;
+    Some(Passage {
+        book: &book.name,
+        chapter: &chapter.number,
+        verses: &chapter.verses[index..index + 1],
+    })
+}
+
+// The same verse for everyone on a given date. The date is hashed rather
+// than used as an index so that consecutive days are not neighbouring
+// verses. Curated verses missing from the Bible are skipped.
+pub fn verse_of_the_day(bible: &Bible, date: Date, pool: Pool) -> Option<Passage<'_>> {
+    let mut rng = SplitMix64::new(date.days_since_epoch() as u64);
+    match pool {
+        Pool::Curated => {
+            let verses: Vec<Passage> = CURATED_VERSES
+                .iter()
+                .filter_map(|reference| find_verse(bible, reference))
+                .collect();
+            (!verses.is_empty()).then(|| verses[rng.below(verses.len())])
+        }
+        Pool::Bible => {
+            let chapters: Vec<(&str, &Chapter)> = bible
+                .books()
+                .flat_map(|book| book.chapters.iter().map(move |c| (book.name.as_str(), c)))
+                .collect();
+            let total = chapters.iter().map(|(_, c)| c.verses.len()).sum();
+            if total == 0 {
+                return None;
+            }
+            let mut index = rng.below(total);
+            for (book, chapter) in chapters {
+                if index < chapter.verses.len() {
+                    return Some(Passage {
+                        book,
+                        chapter: &chapter.number,
+                        verses: &chapter.verses[index..index + 1],
+                    });
+                }
+                index -= chapter.verses.len();
+            }
+            unreachable!()
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct RandomOptions {
+    pub selection: Selection,
+    // Number of consecutive verses, all from one chapter
+    pub verses: usize,
+    // Leave out passages with fewer words, to skip genealogies and the like
+    pub min_words: usize,
+}
+
+impl Default for RandomOptions {
+    fn default() -> Self {
+        RandomOptions {
+            selection: Selection::default(),
+            verses: 1,
+            min_words: 0,
+        }
+    }
+}
+
+// Every passage that meets the options, gathered once so that drawing many
+// passages does not go over the Bible again for each
+pub struct RandomPool<'a> {
+    passages: Vec<Passage<'a>>,
+}
+
+impl<'a> RandomPool<'a> {
+    pub fn new(bible: &'a Bible, options: &RandomOptions) -> Self {
+        let length = options.verses.max(1);
+        let mut passages = Vec::new();
+        for (book, _) in options.selection.books(bible) {
+            for chapter in &book.chapters {
+                // Running word count of each window of `length` verses
+                let counts: Vec<usize> = chapter
+                    .verses
+                    .iter()
+                    .map(|v| words(&v.text).count())
+                    .collect();
+                let mut total: usize = counts.iter().take(length).sum();
+                for (start, verses) in chapter.verses.windows(length).enumerate() {
+                    if start > 0 {
+                        total = total + counts[start + length - 1] - counts[start - 1];
+                    }
+                    if total >= options.min_words {
+                        passages.push(Passage {
+                            book: &book.name,
+                            chapter: &chapter.number,
+                            verses,
+                        });
+                    }
+                }
+            }
+        }
+        RandomPool { passages }
+    }
+
+    pub fn len(&self) -> usize {
+        self.passages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.passages.is_empty()
+    }
+
+    // A passage chosen uniformly from the pool, or None when it is empty
+    pub fn pick(&self, rng: &mut SplitMix64) -> Option<Passage<'a>> {
+        (!self.is_empty()).then(|| self.passages[rng.below(self.len())])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::sample_bible;
+
+    #[test]
+    fn generator_is_reproducible() {
+        // Reference output of splitmix64 seeded with 1234567
+        let mut rng = SplitMix64::new(1234567);
+        assert_eq!(rng.next_u64(), 6457827717110365317);
+        assert_eq!(rng.next_u64(), 3203168211198807973);
+        assert!((0..1000).all(|_| rng.below(7) < 7));
+    }
+
+    #[test]
+    fn picks_the_same_verse_for_a_date() {
+        let bible = sample_bible();
+        let date = Date::new(2025, 12, 25).unwrap();
+        let verse = verse_of_the_day(&bible, date, Pool::Curated).unwrap();
+        assert_eq!(
+            verse.reference(),
+            verse_of_the_day(&bible, date, Pool::Curated)
+                .unwrap()
+                .reference()
+        );
+        // Only these curated verses are in the sample
+        assert!(
+            ["Genesis 1:1", "Genesis 1:3", "Psalms 23:1"].contains(&verse.reference().as_str())
+        );
+
+        let mut seen = Vec::new();
+        for day in 0..60 {
+            let verse = verse_of_the_day(&bible, date.add_days(day), Pool::Bible).unwrap();
+            if !seen.contains(&verse.reference()) {
+                seen.push(verse.reference());
+            }
+        }
+        assert_eq!(seen.len(), 8);
+        let empty = Bible {
+            ot_contents: Vec::new(),
+            ot: Vec::new(),
+            nt_contents: Vec::new(),
+            nt: Vec::new(),
+        };
+        assert!(verse_of_the_day(&empty, date, Pool::Curated).is_none());
+        assert!(verse_of_the_day(&empty, date, Pool::Bible).is_none());
+    }
+
+    #[test]
+    fn respects_random_options() {
+        let bible = sample_bible();
+        let mut options = RandomOptions {
+            selection: "John".parse().unwrap(),
+            verses: 2,
+            ..RandomOptions::default()
+        };
+        let mut rng = SplitMix64::new(7);
+        let pool = RandomPool::new(&bible, &options);
+        assert_eq!(pool.len(), 1);
+        assert_eq!(pool.pick(&mut rng).unwrap().reference(), "John 3:1-2");
+
+        options.selection = Selection::OldTestament;
+        options.verses = 1;
+        options.min_words = 20;
+        let pool = RandomPool::new(&bible, &options);
+        for _ in 0..20 {
+            assert_eq!(pool.pick(&mut rng).unwrap().reference(), "Genesis 1:2");
+        }
+
+        // Genesis 1:1-2 has 39 words and 1:2-3 has 40
+        options.verses = 2;
+        options.min_words = 39;
+        let pool = RandomPool::new(&bible, &options);
+        let references: Vec<String> = pool.passages.iter().map(|p| p.reference()).collect();
+        assert_eq!(references, ["Genesis 1:1-2", "Genesis 1:2-3"]);
+        options.min_words = 40;
+        assert_eq!(RandomPool::new(&bible, &options).len(), 1);
+
+        options.verses = 4;
+        assert!(RandomPool::new(&bible, &options).pick(&mut rng).is_none());
+    }
+}
@@ -0,0 +1,672 @@
+use crate::model::{Bible, Book, Chapter, Verse};
+use crate::reference::VerseRef;
+use crate::search::words;
+use ratatui::Frame;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span, Text};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+// Indices into Bible::books(), a chapter's chapters and its verses
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Location {
+    pub book: usize,
+    pub chapter: usize,
+    pub verse: usize,
+}
+
+// What is remembered between sessions, as references like "John 3:16" so the
+// file stays readable and survives a re-parse
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SavedState {
+    pub position: Option<String>,
+    pub bookmarks: Vec<String>,
+}
+
+impl SavedState {
+    pub fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+// $XDG_STATE_HOME/parse-bible, falling back to ~/.local/state/parse-bible
+pub fn state_dir() -> PathBuf {
+    let base = std::env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".local/state")))
+        .unwrap_or_default();
+    base.join("parse-bible")
+}
+
+pub fn default_state_path() -> PathBuf {
+    state_dir().join("reader.json")
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Focus {
+    Books,
+    Chapters,
+    Text,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Mode {
+    Normal,
+    Jump(String),
+    // The typed query and where the search started, to return there on Esc
+    Search(String, Location),
+}
+
+pub struct Reader<'a> {
+    bible: &'a Bible,
+    books: Vec<&'a Book>,
+    focus: Focus,
+    mode: Mode,
+    location: Location,
+    terms: Vec<String>,
+    hits: Vec<Location>,
+    bookmarks: Vec<Location>,
+    status: String,
+    quit: bool,
+}
+
+// A verse wrapped to `width` columns under a right-aligned verse number.
+// Words matching a search term are highlighted.
+pub fn wrap_verse(
+    verse: &Verse,
+    width: usize,
+    terms: &[String],
+    bookmarked: bool,
+) -> Vec<Line<'static>> {
+    let marker = if bookmarked { "*" } else { " " };
+    let prefix = format!("{}{:>3} ", marker, verse.number);
+    let indent = " ".repeat(prefix.chars().count());
+    let width = width.max(indent.len() + 10);
+
+    let mut lines = Vec::new();
+    let mut line = vec![Span::styled(
+        prefix.clone(),
+        Style::default().add_modifier(Modifier::BOLD),
+    )];
+    let mut used = prefix.chars().count();
+    for word in verse.text.split_whitespace() {
+        let len = word.chars().count();
+        if used > indent.len() && used + 1 + len > width {
+            lines.push(Line::from(std::mem::take(&mut line)));
+            line.push(Span::raw(indent.clone()));
+            used = indent.len();
+        }
+        if used > indent.len() {
+            line.push(Span::raw(" "));
+            used += 1;
+        }
+        let style = if words(word).any(|w| terms.contains(&w)) {
+            Style::default().fg(Color::Black).bg(Color::Yellow)
+        } else {
+            Style::default()
+        };
+        line.push(Span::styled(word.to_string(), style));
+        used += len;
+    }
+    lines.push(Line::from(line));
+    lines
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(bible: &'a Bible, saved: &SavedState) -> Self {
+        let mut reader = Reader {
+            bible,
+            books: bible.books().collect(),
+            focus: Focus::Text,
+            mode: Mode::Normal,
+            location: Location::default(),
+            terms: Vec::new(),
+            hits: Vec::new(),
+            bookmarks: Vec::new(),
+            status: String::new(),
+            quit: false,
+        };
+        reader.bookmarks = saved
+            .bookmarks
+            .iter()
+            .filter_map(|r| reader.resolve(r).ok())
+            .collect();
+        if let Some(position) = saved
+            .position
+            .as_deref()
+            .and_then(|r| reader.resolve(r).ok())
+        {
+            reader.location = position;
+        }
+        reader
+    }
+
+    pub fn saved_state(&self) -> SavedState {
+        SavedState {
+            position: Some(self.reference(self.location)),
+            bookmarks: self.bookmarks.iter().map(|l| self.reference(*l)).collect(),
+        }
+    }
+
+    pub fn location(&self) -> Location {
+        self.location
+    }
+
+    pub fn should_quit(&self) -> bool {
+        self.quit
+    }
+
+    fn book(&self) -> &'a Book {
+        self.books[self.location.book]
+    }
+
+    // None in a book without chapters
+    fn chapter(&self) -> Option<&'a Chapter> {
+        self.book().chapters.get(self.location.chapter)
+    }
+
+    fn verses(&self) -> &'a [Verse] {
+        self.chapter().map_or(&[], |c| c.verses.as_slice())
+    }
+
+    fn reference(&self, l: Location) -> String {
+        let book = self.books[l.book];
+        match book.chapters.get(l.chapter) {
+            None => book.name.clone(),
+            Some(chapter) => match chapter.verses.get(l.verse) {
+                None => format!("{} {}", book.name, chapter.number),
+                Some(verse) => format!("{} {}:{}", book.name, chapter.number, verse.number),
+            },
+        }
+    }
+
+    fn resolve(&self, text: &str) -> Result<Location, String> {
+        let reference = VerseRef::parse(text).map_err(|e| e.to_string())?;
+        let book = self
+            .books
+            .iter()
+            .position(|b| b.name == reference.book)
+            .ok_or_else(|| format!("{} is not in this Bible", reference.book))?;
+        let chapter = self.books[book]
+            .chapters
+            .iter()
+            .position(|c| c.number == reference.chapter.to_string())
+            .ok_or_else(|| format!("{} has no chapter {}", reference.book, reference.chapter))?;
+        let verse = match reference.verse {
+            None => 0,
+            Some(verse) => self.books[book].chapters[chapter]
+                .verses
+                .iter()
+                .position(|v| v.number == verse.to_string())
+                .ok_or_else(|| format!("no verse {}", verse))?,
+        };
+        Ok(Location {
+            book,
+            chapter,
+            verse,
+        })
+    }
+
+    // Moves the selection of the focused pane by `delta`
+    fn step(&mut self, delta: isize) {
+        let clamp = |value: usize, len: usize| {
+            (value as isize + delta).clamp(0, len.saturating_sub(1) as isize) as usize
+        };
+        match self.focus {
+            Focus::Books => {
+                let book = clamp(self.location.book, self.books.len());
+                self.location = Location {
+                    book,
+                    ..Location::default()
+                };
+            }
+            Focus::Chapters => {
+                let chapter = clamp(self.location.chapter, self.book().chapters.len());
+                self.location.chapter = chapter;
+                self.location.verse = 0;
+            }
+            Focus::Text => {
+                self.location.verse = clamp(self.location.verse, self.verses().len());
+            }
+        }
+    }
+
+    // Previous or next chapter, crossing into neighbouring books
+    fn turn_chapter(&mut self, forward: bool) {
+        let Location { book, chapter, .. } = self.location;
+        let target = if forward {
+            if chapter + 1 < self.book().chapters.len() {
+                Some((book, chapter + 1))
+            } else {
+                (book + 1 < self.books.len()).then_some((book + 1, 0))
+            }
+        } else if chapter > 0 {
+            Some((book, chapter - 1))
+        } else {
+            book.checked_sub(1)
+                .map(|b| (b, self.books[b].chapters.len().saturating_sub(1)))
+        };
+        if let Some((book, chapter)) = target {
+            self.location = Location {
+                book,
+                chapter,
+                verse: 0,
+            };
+        }
+    }
+
+    // Hits of all words of the query, in canonical order
+    fn find(&self, query: &str) -> Vec<Location> {
+        // Hits come in the same order as the books, so each one is looked
+        // for from the book of the previous hit on
+        let mut book = 0;
+        let mut hits = Vec::new();
+        for hit in self.bible.search(query) {
+            while self.books[book].name != hit.book {
+                book += 1;
+            }
+            let chapters = &self.books[book].chapters;
+            let Some(chapter) = chapters.iter().position(|c| c.number == hit.chapter) else {
+                continue;
+            };
+            let verses = &chapters[chapter].verses;
+            if let Some(verse) = verses.iter().position(|v| std::ptr::eq(v, hit.verse)) {
+                hits.push(Location {
+                    book,
+                    chapter,
+                    verse,
+                });
+            }
+        }
+        hits
+    }
+
+    fn key(l: Location) -> (usize, usize, usize) {
+        (l.book, l.chapter, l.verse)
+    }
+
+    // Jumps to the nearest hit after (or before) `from`, wrapping around.
+    // `from` itself counts when `inclusive` is set.
+    fn jump_to_hit(&mut self, from: Location, forward: bool, inclusive: bool) {
+        let from = Self::key(from);
+        let wanted = |h: &&Location| {
+            let key = Self::key(**h);
+            (inclusive && key == from) || if forward { key > from } else { key < from }
+        };
+        let hit = if forward {
+            self.hits.iter().find(wanted).or(self.hits.first())
+        } else {
+            self.hits.iter().rev().find(wanted).or(self.hits.last())
+        };
+        if let Some(hit) = hit.copied() {
+            self.location = hit;
+            let index = self.hits.iter().position(|h| *h == hit).unwrap_or(0);
+            self.status = format!("match {} of {}", index + 1, self.hits.len());
+        } else {
+            self.status = "no matches".to_string();
+        }
+    }
+
+    fn update_search(&mut self, query: &str, start: Location) {
+        self.terms = words(query).collect();
+        self.hits = self.find(query);
+        if self.terms.is_empty() {
+            self.location = start;
+            self.status.clear();
+        } else {
+            self.jump_to_hit(start, true, true);
+        }
+    }
+
+    fn toggle_bookmark(&mut self) {
+        let here = self.location;
+        if here.verse >= self.verses().len() {
+            self.status = "nothing to bookmark here".to_string();
+        } else if let Some(i) = self.bookmarks.iter().position(|b| *b == here) {
+            self.bookmarks.remove(i);
+            self.status = format!("removed bookmark {}", self.reference(here));
+        } else {
+            self.bookmarks.push(here);
+            self.bookmarks.sort_by_key(|b| Self::key(*b));
+            self.status = format!("bookmarked {}", self.reference(here));
+        }
+    }
+
+    fn next_bookmark(&mut self) {
+        let here = Self::key(self.location);
+        let next = self
+            .bookmarks
+            .iter()
+            .find(|b| Self::key(**b) > here)
+            .or(self.bookmarks.first());
+        match next.copied() {
+            Some(bookmark) => {
+                self.location = bookmark;
+                self.status = format!("bookmark {}", self.reference(bookmark));
+            }
+            None => self.status = "no bookmarks, press m to add one".to_string(),
+        }
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) {
+        match std::mem::replace(&mut self.mode, Mode::Normal) {
+            Mode::Jump(mut input) => match key.code {
+                KeyCode::Enter => match self.resolve(&input) {
+                    Ok(location) => {
+                        self.location = location;
+                        self.focus = Focus::Text;
+                        self.status = format!("at {}", self.reference(location));
+                    }
+                    Err(e) => self.status = e,
+                },
+                KeyCode::Esc => self.status.clear(),
+                KeyCode::Backspace => {
+                    input.pop();
+                    self.mode = Mode::Jump(input);
+                }
+                KeyCode::Char(c) => {
+                    input.push(c);
+                    self.mode = Mode::Jump(input);
+                }
+                _ => self.mode = Mode::Jump(input),
+            },
+            Mode::Search(mut input, start) => match key.code {
+                KeyCode::Enter => self.focus = Focus::Text,
+                KeyCode::Esc => {
+                    self.location = start;
+                    self.terms.clear();
+                    self.hits.clear();
+                    self.status.clear();
+                }
+                KeyCode::Backspace => {
+                    input.pop();
+                    self.update_search(&input, start);
+                    self.mode = Mode::Search(input, start);
+                }
+                KeyCode::Char(c) => {
+                    input.push(c);
+                    self.update_search(&input, start);
+                    self.mode = Mode::Search(input, start);
+                }
+                _ => self.mode = Mode::Search(input, start),
+            },
+            Mode::Normal => match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+                KeyCode::Tab | KeyCode::Enter | KeyCode::Right | KeyCode::Char('l') => {
+                    self.focus = match self.focus {
+                        Focus::Books => Focus::Chapters,
+                        Focus::Chapters | Focus::Text => Focus::Text,
+                    }
+                }
+                KeyCode::BackTab | KeyCode::Left | KeyCode::Char('h') => {
+                    self.focus = match self.focus {
+                        Focus::Text => Focus::Chapters,
+                        Focus::Chapters | Focus::Books => Focus::Books,
+                    }
+                }
+                KeyCode::Down | KeyCode::Char('j') => self.step(1),
+                KeyCode::Up | KeyCode::Char('k') => self.step(-1),
+                KeyCode::PageDown => self.step(10),
+                KeyCode::PageUp => self.step(-10),
+                KeyCode::Char(']') => self.turn_chapter(true),
+                KeyCode::Char('[') => self.turn_chapter(false),
+                KeyCode::Char('g') | KeyCode::Char(':') => self.mode = Mode::Jump(String::new()),
+                KeyCode::Char('/') => self.mode = Mode::Search(String::new(), self.location),
+                KeyCode::Char('n') => self.jump_to_hit(self.location, true, false),
+                KeyCode::Char('N') => self.jump_to_hit(self.location, false, false),
+                KeyCode::Char('m') => self.toggle_bookmark(),
+                KeyCode::Char('\'') => self.next_bookmark(),
+                _ => {}
+            },
+        }
+    }
+
+    pub fn draw(&self, frame: &mut Frame) {
+        let [main, footer] =
+            Layout::vertical([Constraint::Min(3), Constraint::Length(1)]).areas(frame.area());
+        let [books_area, chapters_area, text_area] = Layout::horizontal([
+            Constraint::Length(20),
+            Constraint::Length(9),
+            Constraint::Min(20),
+        ])
+        .areas(main);
+
+        let pane = |title: String, focus: Focus| {
+            let style = if self.focus == focus {
+                Style::default().fg(Color::Cyan)
+            } else {
+                Style::default()
+            };
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(style)
+                .title(title)
+        };
+        let highlight = Style::default().add_modifier(Modifier::REVERSED);
+
+        let books = List::new(self.books.iter().map(|b| b.name.as_str()))
+            .block(pane("Books".to_string(), Focus::Books))
+            .highlight_style(highlight);
+        let mut state = ListState::default().with_selected(Some(self.location.book));
+        frame.render_stateful_widget(books, books_area, &mut state);
+
+        let chapters = List::new(self.book().chapters.iter().map(|c| c.number.as_str()))
+            .block(pane("Ch".to_string(), Focus::Chapters))
+            .highlight_style(highlight);
+        let mut state = ListState::default().with_selected(Some(self.location.chapter));
+        frame.render_stateful_widget(chapters, chapters_area, &mut state);
+
+        let width = text_area.width.saturating_sub(2) as usize;
+        let verses = self.verses().iter().enumerate().map(|(i, verse)| {
+            let here = Location {
+                verse: i,
+                ..self.location
+            };
+            ListItem::new(Text::from(wrap_verse(
+                verse,
+                width,
+                &self.terms,
+                self.bookmarks.contains(&here),
+            )))
+        });
+        let title = match self.chapter() {
+            Some(chapter) => format!("{} {}", self.book().name, chapter.number),
+            None => self.book().name.clone(),
+        };
+        let text = List::new(verses)
+            .block(pane(title, Focus::Text))
+            .highlight_style(Style::default().bg(Color::DarkGray));
+        let mut state = ListState::default().with_selected(Some(self.location.verse));
+        frame.render_stateful_widget(text, text_area, &mut state);
+
+        let footer_text = match &self.mode {
+            Mode::Jump(input) => format!("Go to: {}", input),
+            Mode::Search(input, _) => format!("/{}  {}", input, self.status),
+            Mode::Normal if !self.status.is_empty() => self.status.clone(),
+            Mode::Normal => "q quit  tab/h/l panes  j/k move  [ ] chapter  g go to  / search  n/N next/prev  m bookmark  ' bookmarks".to_string(),
+        };
+        frame.render_widget(Paragraph::new(footer_text), footer);
+    }
+}
+
+// Runs the full-screen reader until q is pressed, then saves the position
+// and bookmarks to `state_path`
+pub fn run_reader(bible: &Bible, state_path: &Path) -> Result<(), Box<dyn Error>> {
+    if bible.books().next().is_none() {
+        return Err("the bible has no books".into());
+    }
+    let mut reader = Reader::new(bible, &SavedState::load(state_path));
+
+    let mut terminal = ratatui::init();
+    let result = (|| -> Result<(), Box<dyn Error>> {
+        while !reader.should_quit() {
+            terminal.draw(|frame| reader.draw(frame))?;
+            if let Event::Key(key) = event::read()?
+                && key.kind == KeyEventKind::Press
+            {
+                reader.handle_key(key);
+            }
+        }
+        Ok(())
+    })();
+    ratatui::restore();
+
+    result?;
+    reader.saved_state().save(state_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::sample_bible;
+    use ratatui::Terminal;
+    use ratatui::backend::TestBackend;
+
+    fn press(reader: &mut Reader, keys: &str) {
+        for c in keys.chars() {
+            let code = match c {
+                '\n' => KeyCode::Enter,
+                '\x1b' => KeyCode::Esc,
+                '\x08' => KeyCode::Backspace,
+                c => KeyCode::Char(c),
+            };
+            reader.handle_key(KeyEvent::from(code));
+        }
+    }
+
+    #[test]
+    fn wraps_verses_and_highlights_terms() {
+        let verse = Verse {
+            number: "3".to_string(),
+            text: "And God said, Let there be light: and there was light.".to_string(),
+        };
+        let lines = wrap_verse(&verse, 24, &["light".to_string()], true);
+        let rendered: Vec<String> = lines.iter().map(|l| l.to_string()).collect();
+        assert_eq!(
+            rendered,
+            vec![
+                "*  3 And God said, Let",
+                "     there be light: and",
+                "     there was light.",
+            ]
+        );
+        let highlighted: Vec<&str> = lines
+            .iter()
+            .flat_map(|l| &l.spans)
+            .filter(|s| s.style.bg == Some(Color::Yellow))
+            .map(|s| s.content.as_ref())
+            .collect();
+        assert_eq!(highlighted, vec!["light:", "light."]);
+    }
+
+    #[test]
+    fn navigates_jumps_and_searches() {
+        let bible = sample_bible();
+        let mut reader = Reader::new(&bible, &SavedState::default());
+
+        press(&mut reader, "gPs 23:2\n");
+        assert_eq!(reader.reference(reader.location()), "Psalms 23:2");
+        press(&mut reader, "gJohn 9\n");
+        assert_eq!(reader.status, "John has no chapter 9");
+
+        press(&mut reader, "]");
+        assert_eq!(reader.reference(reader.location()), "John 3:1");
+        press(&mut reader, "[[");
+        assert_eq!(reader.reference(reader.location()), "Genesis 2:1");
+
+        // Incremental: the view follows each keystroke, Esc goes back
+        press(&mut reader, "gPs 23:1\n/ear");
+        assert_eq!(reader.status, "no matches");
+        press(&mut reader, "th");
+        assert_eq!(reader.reference(reader.location()), "Genesis 1:1");
+        assert_eq!(reader.status, "match 1 of 3");
+        press(&mut reader, "\x1b");
+        assert_eq!(reader.reference(reader.location()), "Psalms 23:1");
+        assert!(reader.terms.is_empty());
+
+        press(&mut reader, "/earth\nn");
+        assert_eq!(reader.reference(reader.location()), "Genesis 1:2");
+        press(&mut reader, "N");
+        assert_eq!(reader.reference(reader.location()), "Genesis 1:1");
+        press(&mut reader, "/light\n");
+        assert_eq!(reader.reference(reader.location()), "Genesis 1:3");
+        assert_eq!(reader.terms, vec!["light"]);
+        assert!(!reader.should_quit());
+        press(&mut reader, "q");
+        assert!(reader.should_quit());
+    }
+
+    #[test]
+    fn remembers_bookmarks_and_position() {
+        let bible = sample_bible();
+        let mut reader = Reader::new(&bible, &SavedState::default());
+        press(&mut reader, "jm");
+        press(&mut reader, "gJohn 3:2\nm");
+        press(&mut reader, "'");
+        assert_eq!(reader.reference(reader.location()), "Genesis 1:2");
+
+        let path = std::env::temp_dir().join(format!("kjv-reader-{}.json", std::process::id()));
+        reader.saved_state().save(&path).unwrap();
+        let saved = SavedState::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(saved.position.as_deref(), Some("Genesis 1:2"));
+        assert_eq!(saved.bookmarks, vec!["Genesis 1:2", "John 3:2"]);
+
+        let restored = Reader::new(&bible, &saved);
+        assert_eq!(restored.location(), reader.location());
+        assert_eq!(restored.bookmarks.len(), 2);
+    }
+
+    #[test]
+    fn survives_empty_books_and_chapters() {
+        let mut bible = sample_bible();
+        bible.ot[1].chapters[0].verses.clear();
+        bible.nt[0].chapters.clear();
+        let mut reader = Reader::new(&bible, &SavedState::default());
+        let mut terminal = Terminal::new(TestBackend::new(80, 12)).unwrap();
+
+        press(&mut reader, "]]");
+        assert_eq!(reader.reference(reader.location()), "Psalms 23");
+        press(&mut reader, "jkm]jk");
+        assert_eq!(reader.status, "nothing to bookmark here");
+        assert_eq!(reader.reference(reader.location()), "John");
+        terminal.draw(|frame| reader.draw(frame)).unwrap();
+        press(&mut reader, "hjkhj[[");
+        assert_eq!(reader.reference(reader.location()), "Genesis 2:1");
+        press(&mut reader, "/earth\n");
+        assert_eq!(reader.status, "match 3 of 3");
+    }
+
+    #[test]
+    fn draws_panes() {
+        let bible = sample_bible();
+        let reader = Reader::new(&bible, &SavedState::default());
+        let mut terminal = Terminal::new(TestBackend::new(80, 12)).unwrap();
+        terminal.draw(|frame| reader.draw(frame)).unwrap();
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(screen.contains("Genesis 1"));
+        assert!(screen.contains("Psalms"));
+        assert!(screen.contains("In the beginning"));
+    }
+}
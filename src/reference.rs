@@ -0,0 +1,503 @@
+use crate::model::{Bible, Verse};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use core::str::FromStr;
+
+// Canonical book name (as produced by the parser), OSIS ID, and extra
+// abbreviations. Abbreviations are written in normalized form: lowercase,
+// no dots or spaces, numbered books prefixed with their digit.
+const BOOKS: [(&str, &str, &[&str]); 66] = [
+    ("Genesis", "Gen", &["gn", "ge"]),
+    ("Exodus", "Exod", &["ex", "exo"]),
+    ("Leviticus", "Lev", &["le", "lv"]),
+    ("Numbers", "Num", &["nu", "nm", "nb"]),
+    ("Deuteronomy", "Deut", &["de", "dt"]),
+    ("Joshua", "Josh", &["jos", "jsh"]),
+    ("Judges", "Judg", &["jdg", "jg", "jdgs"]),
+    ("Ruth", "Ruth", &["rth", "ru"]),
+    ("1 Samuel", "1Sam", &["1sa", "1sm"]),
+    ("2 Samuel", "2Sam", &["2sa", "2sm"]),
+    ("1 Kings", "1Kgs", &["1ki", "1kin", "1king"]),
+    ("2 Kings", "2Kgs", &["2ki", "2kin", "2king"]),
+    ("1 Chronicles", "1Chr", &["1ch", "1chron"]),
+    ("2 Chronicles", "2Chr", &["2ch", "2chron"]),
+    ("Ezra", "Ezra", &["ezr"]),
+    ("Nehemiah", "Neh", &["ne"]),
+    ("Esther", "Esth", &["est", "es"]),
+    ("Job", "Job", &["jb"]),
+    ("Psalms", "Ps", &["psalm", "psa", "pss", "psm"]),
+    ("Proverbs", "Prov", &["pro", "prv", "pr"]),
+    ("Ecclesiastes", "Eccl", &["ecc", "ec", "eccles", "qoh"]),
+    (
+        "Song of Solomon",
+        "Song",
+        &["songofsongs", "sos", "canticles", "cant"],
+    ),
+    ("Isaiah", "Isa", &[]),
+    ("Jeremiah", "Jer", &["je", "jr"]),
+    ("Lamentations", "Lam", &["la"]),
+    ("Ezekiel", "Ezek", &["eze", "ezk"]),
+    ("Daniel", "Dan", &["da", "dn"]),
+    ("Hosea", "Hos", &["ho"]),
+    ("Joel", "Joel", &["jl"]),
+    ("Amos", "Amos", &[]),
+    ("Obadiah", "Obad", &["ob", "oba"]),
+    ("Jonah", "Jonah", &["jon", "jnh"]),
+    ("Micah", "Mic", &["mc"]),
+    ("Nahum", "Nah", &["na"]),
+    ("Habakkuk", "Hab", &["hb"]),
+    ("Zephaniah", "Zeph", &["zep", "zp"]),
+    ("Haggai", "Hag", &["hg"]),
+    ("Zechariah", "Zech", &["zec", "zc"]),
+    ("Malachi", "Mal", &["ml"]),
+    ("Matthew", "Matt", &["mt", "mat"]),
+    ("Mark", "Mark", &["mk", "mr", "mar"]),
+    ("Luke", "Luke", &["lk", "luk"]),
+    ("John", "John", &["jn", "jhn", "joh"]),
+    ("Acts", "Acts", &["ac", "act"]),
+    ("Romans", "Rom", &["ro", "rm"]),
+    ("1 Corinthians", "1Cor", &["1co"]),
+    ("2 Corinthians", "2Cor", &["2co"]),
+    ("Galatians", "Gal", &["ga"]),
+    ("Ephesians", "Eph", &["ephes"]),
+    ("Philippians", "Phil", &["php", "pp"]),
+    ("Colossians", "Col", &[]),
+    ("1 Thessalonians", "1Thess", &["1th", "1thes"]),
+    ("2 Thessalonians", "2Thess", &["2th", "2thes"]),
+    ("1 Timothy", "1Tim", &["1ti"]),
+    ("2 Timothy", "2Tim", &["2ti"]),
+    ("Titus", "Titus", &["tit"]),
+    ("Philemon", "Phlm", &["philem", "phm", "pm"]),
+    ("Hebrews", "Heb", &[]),
+    ("James", "Jas", &["jm", "jam"]),
+    ("1 Peter", "1Pet", &["1pe", "1pt", "1p"]),
+    ("2 Peter", "2Pet", &["2pe", "2pt", "2p"]),
+    ("1 John", "1John", &["1jn", "1jo", "1jhn", "1j"]),
+    ("2 John", "2John", &["2jn", "2jo", "2jhn"]),
+    ("3 John", "3John", &["3jn", "3jo", "3jhn"]),
+    ("Jude", "Jude", &["jud", "jd"]),
+    (
+        "Revelation",
+        "Rev",
+        &["re", "rv", "revelations", "apocalypse"],
+    ),
+];
+
+// The 66 canonical book names in order
+pub fn book_names() -> impl Iterator<Item = &'static str> {
+    BOOKS.iter().map(|(name, _, _)| *name)
+}
+
+pub fn osis_id(book: &str) -> Option<&'static str> {
+    BOOKS
+        .iter()
+        .find(|(name, _, _)| *name == book)
+        .map(|(_, osis, _)| *osis)
+}
+
+// Lowercases, drops dots and spaces, and turns leading ordinals ("I", "First",
+// "1st") into digits, so "I Cor." and "1 Corinthians" compare equal
+pub(crate) fn normalize_book(name: &str) -> String {
+    let lower = name.to_lowercase().replace('.', "");
+    let mut words = lower.split_whitespace();
+    let mut out = String::new();
+    if let Some(first) = words.next() {
+        out.push_str(match first {
+            "i" | "first" | "1st" => "1",
+            "ii" | "second" | "2nd" => "2",
+            "iii" | "third" | "3rd" => "3",
+            other => other,
+        });
+    }
+    for word in words {
+        out.push_str(word);
+    }
+    out
+}
+
+// Resolves a full name, OSIS ID or common abbreviation to the canonical name
+pub fn resolve_book(name: &str) -> Option<&'static str> {
+    let key = normalize_book(name);
+    if key.is_empty() {
+        return None;
+    }
+    BOOKS
+        .iter()
+        .find(|(canonical, osis, aliases)| {
+            normalize_book(canonical) == key
+                || normalize_book(osis) == key
+                || aliases.contains(&key.as_str())
+        })
+        .map(|(canonical, _, _)| *canonical)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ReferenceError {
+    InvalidReference(String),
+    UnknownBook(String),
+    ChapterOutOfRange {
+        book: &'static str,
+        chapter: u32,
+        chapters: usize,
+    },
+    VerseOutOfRange {
+        book: &'static str,
+        chapter: u32,
+        verse: u32,
+        verses: usize,
+    },
+}
+
+impl fmt::Display for ReferenceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReferenceError::InvalidReference(s) => write!(f, "invalid reference '{}'", s),
+            ReferenceError::UnknownBook(s) => write!(f, "unknown book '{}'", s),
+            ReferenceError::ChapterOutOfRange {
+                book,
+                chapter,
+                chapters,
+            } => write!(
+                f,
+                "{} has {} chapters, there is no chapter {}",
+                book, chapters, chapter
+            ),
+            ReferenceError::VerseOutOfRange {
+                book,
+                chapter,
+                verse,
+                verses,
+            } => write!(
+                f,
+                "{} {} has {} verses, there is no verse {}",
+                book, chapter, verses, verse
+            ),
+        }
+    }
+}
+
+impl core::error::Error for ReferenceError {}
+
+// A chapter, verse or verse range within one chapter, e.g. "John 3",
+// "John 3:16" or "John 3:16-18"
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VerseRef {
+    pub book: &'static str,
+    pub chapter: u32,
+    pub verse: Option<u32>, // None refers to the whole chapter
+    pub end_verse: Option<u32>,
+}
+
+impl VerseRef {
+    pub fn parse(s: &str) -> Result<Self, ReferenceError> {
+        let invalid = || ReferenceError::InvalidReference(s.trim().to_string());
+
+        let s = s.trim();
+        let (book, location) = s.rsplit_once(char::is_whitespace).ok_or_else(invalid)?;
+        let book = resolve_book(book)
+            .ok_or_else(|| ReferenceError::UnknownBook(book.trim().to_string()))?;
+
+        let number = |n: &str| n.trim().parse::<u32>().ok().filter(|n| *n > 0);
+        let (chapter, verses) = match location.split_once(':') {
+            Some((chapter, verses)) => (chapter, Some(verses)),
+            None => (location, None),
+        };
+        let chapter = number(chapter).ok_or_else(invalid)?;
+
+        let (verse, end_verse) = match verses {
+            None => (None, None),
+            Some(verses) => match verses.split_once(['-', '–']) {
+                Some((start, end)) => {
+                    let (start, end) = (
+                        number(start).ok_or_else(invalid)?,
+                        number(end).ok_or_else(invalid)?,
+                    );
+                    if end < start {
+                        return Err(invalid());
+                    }
+                    (Some(start), Some(end))
+                }
+                None => (Some(number(verses).ok_or_else(invalid)?), None),
+            },
+        };
+
+        Ok(VerseRef {
+            book,
+            chapter,
+            verse,
+            end_verse,
+        })
+    }
+
+    // OSIS reference such as "John.3.16", "John.3.16-John.3.18" or "Ps.23"
+    pub fn osis_ref(&self) -> String {
+        let book = osis_id(self.book).unwrap_or(self.book);
+        let mut osis = format!("{}.{}", book, self.chapter);
+        if let Some(verse) = self.verse {
+            osis.push_str(&format!(".{}", verse));
+        }
+        if let Some(end) = self.end_verse.filter(|end| Some(*end) != self.verse) {
+            osis.push_str(&format!("-{}.{}.{}", book, self.chapter, end));
+        }
+        osis
+    }
+
+    pub fn is_single_verse(&self) -> bool {
+        self.verse.is_some() && self.end_verse.is_none_or(|end| Some(end) == self.verse)
+    }
+}
+
+impl FromStr for VerseRef {
+    type Err = ReferenceError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        VerseRef::parse(s)
+    }
+}
+
+impl fmt::Display for VerseRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.book, self.chapter)?;
+        if let Some(verse) = self.verse {
+            write!(f, ":{}", verse)?;
+        }
+        if let Some(end) = self.end_verse.filter(|end| Some(*end) != self.verse) {
+            write!(f, "-{}", end)?;
+        }
+        Ok(())
+    }
+}
+
+// A reference found in free text, with the byte range it spans
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReferenceMatch {
+    pub start: usize,
+    pub end: usize,
+    pub reference: VerseRef,
+}
+
+// Start offsets of the (up to three) words before `end`, nearest first, so
+// that multi-word names such as "Song of Solomon" or "1 Cor" can be tried
+fn preceding_words(text: &str, mut end: usize) -> Vec<usize> {
+    let mut starts = Vec::new();
+    while starts.len() < 3 && end > 0 && text[..end].ends_with(' ') {
+        let word_end = end - 1;
+        let start = text[..word_end].rfind(char::is_whitespace).map_or(0, |p| {
+            p + text[p..].chars().next().map_or(1, char::len_utf8)
+        });
+        // Drop opening punctuation such as "(" and stop there
+        let trimmed = text[start..word_end]
+            .find(char::is_alphanumeric)
+            .map(|p| start + p);
+        match trimmed {
+            Some(trimmed) => starts.push(trimmed),
+            None => break,
+        }
+        if trimmed != Some(start) {
+            break;
+        }
+        end = start;
+    }
+    starts
+}
+
+// Finds references like "Romans 8:28", "1 Cor. 13:4-7" or "Psalm 23" in text.
+// Only book names that resolve are considered; chapters and verses are not
+// checked against a Bible.
+pub fn find_references(text: &str) -> Vec<ReferenceMatch> {
+    let bytes = text.as_bytes();
+    let mut found = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        if !bytes[i].is_ascii_digit() || i == 0 || bytes[i - 1] != b' ' {
+            i += 1;
+            continue;
+        }
+        let location_len: usize = text[i..]
+            .chars()
+            .take_while(|c| c.is_ascii_digit() || matches!(c, ':' | '-' | '–'))
+            .map(char::len_utf8)
+            .sum();
+        let location = text[i..i + location_len].trim_end_matches([':', '-', '–']);
+        let end = i + location.len();
+        let next = text[i + location_len..].chars().next();
+        if next.is_some_and(char::is_alphanumeric) {
+            i += location_len;
+            continue;
+        }
+
+        // Longest book name first, so "1 John 3:16" is not read as "John 3:16"
+        for start in preceding_words(text, i).into_iter().rev() {
+            let book = &text[start..i - 1];
+            if resolve_book(book).is_none() {
+                continue;
+            }
+            if let Ok(reference) = VerseRef::parse(&format!("{} {}", book, location)) {
+                found.push(ReferenceMatch {
+                    start,
+                    end,
+                    reference,
+                });
+                break;
+            }
+        }
+        i += location_len;
+    }
+    found
+}
+
+impl Bible {
+    // Verses covered by the reference, checking the chapter and verses exist
+    pub fn lookup(&self, reference: &VerseRef) -> Result<Vec<&Verse>, ReferenceError> {
+        let book = self
+            .book(reference.book)
+            .ok_or_else(|| ReferenceError::UnknownBook(reference.book.to_string()))?;
+        let chapter = book.chapter(&reference.chapter.to_string()).ok_or(
+            ReferenceError::ChapterOutOfRange {
+                book: reference.book,
+                chapter: reference.chapter,
+                chapters: book.chapters.len(),
+            },
+        )?;
+
+        let Some(start) = reference.verse else {
+            return Ok(chapter.verses.iter().collect());
+        };
+        let end = reference.end_verse.unwrap_or(start);
+        for verse in [start, end] {
+            if chapter.verse(&verse.to_string()).is_none() {
+                return Err(ReferenceError::VerseOutOfRange {
+                    book: reference.book,
+                    chapter: reference.chapter,
+                    verse,
+                    verses: chapter.verses.len(),
+                });
+            }
+        }
+
+        Ok(chapter
+            .verses
+            .iter()
+            .filter(|v| {
+                v.number
+                    .parse::<u32>()
+                    .is_ok_and(|n| n >= start && n <= end)
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::sample_bible;
+
+    #[test]
+    fn resolves_book_names_and_abbreviations() {
+        assert_eq!(resolve_book("Genesis"), Some("Genesis"));
+        assert_eq!(resolve_book("gen."), Some("Genesis"));
+        assert_eq!(resolve_book("Ps"), Some("Psalms"));
+        assert_eq!(resolve_book("Psalm"), Some("Psalms"));
+        assert_eq!(resolve_book("I Cor."), Some("1 Corinthians"));
+        assert_eq!(resolve_book("1cor"), Some("1 Corinthians"));
+        assert_eq!(resolve_book("Second Kings"), Some("2 Kings"));
+        assert_eq!(resolve_book("song of songs"), Some("Song of Solomon"));
+        assert_eq!(resolve_book("1John"), Some("1 John"));
+        assert_eq!(resolve_book("Hezekiah"), None);
+        assert_eq!(book_names().count(), 66);
+        assert_eq!(osis_id("Song of Solomon"), Some("Song"));
+    }
+
+    #[test]
+    fn parses_references() {
+        assert_eq!(
+            VerseRef::parse("John 3:16"),
+            Ok(VerseRef {
+                book: "John",
+                chapter: 3,
+                verse: Some(16),
+                end_verse: None,
+            })
+        );
+        let range: VerseRef = "1 Cor 13:4-7".parse().unwrap();
+        assert_eq!(range.book, "1 Corinthians");
+        assert_eq!((range.verse, range.end_verse), (Some(4), Some(7)));
+        assert_eq!(range.to_string(), "1 Corinthians 13:4-7");
+        assert!(!range.is_single_verse());
+
+        let chapter = VerseRef::parse("Psalm 23").unwrap();
+        assert_eq!((chapter.chapter, chapter.verse), (23, None));
+        assert_eq!(chapter.to_string(), "Psalms 23");
+
+        assert!(matches!(
+            VerseRef::parse("Hezekiah 1:1"),
+            Err(ReferenceError::UnknownBook(_))
+        ));
+        for invalid in ["John", "John 0", "John 3:x", "John 3:18-16", ""] {
+            assert!(VerseRef::parse(invalid).is_err(), "{}", invalid);
+        }
+
+        let osis = |s: &str| VerseRef::parse(s).unwrap().osis_ref();
+        assert_eq!(osis("Ps 23"), "Ps.23");
+        assert_eq!(osis("1 Cor 13:4"), "1Cor.13.4");
+        assert_eq!(osis("John 3:16-18"), "John.3.16-John.3.18");
+    }
+
+    #[test]
+    fn looks_up_references() {
+        let bible = sample_bible();
+
+        let verses = bible
+            .lookup(&VerseRef::parse("Gen 1:2-3").unwrap())
+            .unwrap();
+        assert_eq!(verses.len(), 2);
+        assert_eq!(
+            verses[1].text,
+            "And God said, Let there be light: and there was light."
+        );
+        assert_eq!(
+            bible
+                .lookup(&VerseRef::parse("Genesis 1").unwrap())
+                .unwrap()
+                .len(),
+            3
+        );
+
+        assert_eq!(
+            bible.lookup(&VerseRef::parse("Genesis 4:1").unwrap()).err(),
+            Some(ReferenceError::ChapterOutOfRange {
+                book: "Genesis",
+                chapter: 4,
+                chapters: 2,
+            })
+        );
+        assert!(matches!(
+            bible.lookup(&VerseRef::parse("Psalms 23:7").unwrap()),
+            Err(ReferenceError::VerseOutOfRange { verse: 7, .. })
+        ));
+        assert!(matches!(
+            bible.lookup(&VerseRef::parse("Exodus 1:1").unwrap()),
+            Err(ReferenceError::UnknownBook(_))
+        ));
+    }
+
+    #[test]
+    fn finds_references_in_text() {
+        let text = "As Romans 8:28 says (cf. 1 Cor. 13:4-7), read Song of Solomon 2 and\nJohn 3:16.\nNot a reference: page 12, Genesis 3rd.";
+        let found: Vec<(&str, String)> = find_references(text)
+            .iter()
+            .map(|m| (&text[m.start..m.end], m.reference.to_string()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("Romans 8:28", "Romans 8:28".to_string()),
+                ("1 Cor. 13:4-7", "1 Corinthians 13:4-7".to_string()),
+                ("Song of Solomon 2", "Song of Solomon 2".to_string()),
+                ("John 3:16", "John 3:16".to_string()),
+            ]
+        );
+    }
+}
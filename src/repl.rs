@@ -0,0 +1,449 @@
+use crate::model::{Bible, Book, Chapter};
+use crate::reader::state_dir;
+use crate::reference::{VerseRef, book_names, resolve_book};
+use crate::search::words;
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+use std::error::Error;
+
+// Hits printed by search before the rest are summarized
+const SEARCH_LIMIT: usize = 20;
+
+const COMMANDS: [&str; 7] = ["search", "next", "prev", "stats", "books", "help", "quit"];
+
+const HELP: &str = "\
+John 3:16-21              show a passage, verse or chapter
+search grace NEAR/3 faith verses with all words, NEAR/n keeps two words close
+                          (the first 20 are shown)
+next, prev                move on from the last passage
+stats [Psalms]            counts for a book or the whole Bible
+books                     list the books
+quit                      leave (or Ctrl-D)";
+
+// "1 verse", "7 verses"
+fn count_of(count: usize, noun: &str) -> String {
+    if count == 1 {
+        format!("1 {}", noun)
+    } else {
+        format!("{} {}s", count, noun)
+    }
+}
+
+// Book names and commands completing the text before the cursor. Returns the
+// offset the candidates replace from, so "1 Co" completes to "1 Corinthians".
+pub fn complete(line: &str, pos: usize) -> (usize, Vec<String>) {
+    let prefix = &line[..pos];
+    let mut starts: Vec<usize> = prefix.match_indices(' ').map(|(i, _)| i + 1).collect();
+    starts.insert(0, 0);
+    // Longest partial first, over at most the last three words
+    for &start in &starts[starts.len().saturating_sub(3)..] {
+        let partial = prefix[start..].to_lowercase();
+        if partial.is_empty() {
+            continue;
+        }
+        let commands = COMMANDS.iter().filter(|_| start == 0).copied();
+        let matches: Vec<String> = commands
+            .chain(book_names())
+            .filter(|name| name.to_lowercase().starts_with(&partial))
+            .map(str::to_string)
+            .collect();
+        if !matches.is_empty() {
+            return (start, matches);
+        }
+    }
+    (pos, Vec::new())
+}
+
+struct ReplHelper;
+
+impl Completer for ReplHelper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        Ok(complete(line, pos))
+    }
+}
+
+impl Hinter for ReplHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ReplHelper {}
+
+impl Validator for ReplHelper {}
+
+impl Helper for ReplHelper {}
+
+pub struct Repl<'a> {
+    bible: &'a Bible,
+    books: Vec<&'a Book>,
+    // The last passage shown, what next and prev move on from
+    current: Option<VerseRef>,
+    search_limit: usize,
+}
+
+impl<'a> Repl<'a> {
+    pub fn new(bible: &'a Bible) -> Self {
+        Repl {
+            bible,
+            books: bible.books().collect(),
+            current: None,
+            search_limit: SEARCH_LIMIT,
+        }
+    }
+
+    fn show(&mut self, reference: VerseRef) -> Result<String, String> {
+        let verses = self.bible.lookup(&reference).map_err(|e| e.to_string())?;
+        self.current = Some(reference);
+        let mut out = reference.to_string();
+        for verse in verses {
+            out.push_str(&format!("\n{:>3} {}", verse.number, verse.text));
+        }
+        Ok(out)
+    }
+
+    fn last_verse(chapter: &Chapter) -> u32 {
+        chapter
+            .verses
+            .last()
+            .and_then(|v| v.number.parse().ok())
+            .unwrap_or(1)
+    }
+
+    // The chapter before or after, crossing books, with its last verse number
+    fn neighbour(&self, reference: &VerseRef, forward: bool) -> Option<(&'static str, u32, u32)> {
+        let b = self.books.iter().position(|b| b.name == reference.book)?;
+        let c = self.books[b]
+            .chapters
+            .iter()
+            .position(|c| c.number == reference.chapter.to_string())?;
+        let (b, c) = if forward {
+            if c + 1 < self.books[b].chapters.len() {
+                (b, c + 1)
+            } else {
+                (b + 1, 0)
+            }
+        } else if c > 0 {
+            (b, c - 1)
+        } else {
+            let b = b.checked_sub(1)?;
+            (b, self.books[b].chapters.len().checked_sub(1)?)
+        };
+        let book = self.books.get(b)?;
+        let chapter = book.chapters.get(c)?;
+        Some((
+            resolve_book(&book.name)?,
+            chapter.number.parse().ok()?,
+            Self::last_verse(chapter),
+        ))
+    }
+
+    // The passage of the same size right after (or before) `reference`
+    fn shift(&self, reference: &VerseRef, forward: bool) -> Option<VerseRef> {
+        let Some(start) = reference.verse else {
+            let (book, chapter, _) = self.neighbour(reference, forward)?;
+            return Some(VerseRef {
+                book,
+                chapter,
+                verse: None,
+                end_verse: None,
+            });
+        };
+        let end = reference.end_verse.unwrap_or(start);
+        let len = end - start;
+        let chapter = self
+            .bible
+            .chapter(reference.book, &reference.chapter.to_string())?;
+        let last = chapter.verses.last()?.number.parse().ok()?;
+
+        let (book, chapter, start, end) = if forward {
+            if end < last {
+                let start = end + 1;
+                (
+                    reference.book,
+                    reference.chapter,
+                    start,
+                    (start + len).min(last),
+                )
+            } else {
+                let (book, chapter, last) = self.neighbour(reference, true)?;
+                (book, chapter, 1, (1 + len).min(last))
+            }
+        } else if start > 1 {
+            let end = start - 1;
+            (
+                reference.book,
+                reference.chapter,
+                end.saturating_sub(len).max(1),
+                end,
+            )
+        } else {
+            let (book, chapter, last) = self.neighbour(reference, false)?;
+            (book, chapter, last.saturating_sub(len).max(1), last)
+        };
+        Some(VerseRef {
+            book,
+            chapter,
+            verse: Some(start),
+            end_verse: (end != start).then_some(end),
+        })
+    }
+
+    fn stats(&self, name: &str) -> Result<String, String> {
+        let books: Vec<&Book> = if name.is_empty() {
+            self.books.clone()
+        } else {
+            let canonical = resolve_book(name).ok_or_else(|| format!("unknown book '{}'", name))?;
+            vec![
+                self.bible
+                    .book(canonical)
+                    .ok_or_else(|| format!("{} is not in this Bible", canonical))?,
+            ]
+        };
+
+        let (mut chapters, mut verses, mut word_count) = (0, 0, 0);
+        let mut longest_chapter: Option<(String, usize)> = None;
+        let mut shortest_chapter: Option<(String, usize)> = None;
+        let mut longest_verse: Option<(String, usize)> = None;
+        for book in &books {
+            for chapter in &book.chapters {
+                chapters += 1;
+                verses += chapter.verses.len();
+                let name = format!("{} {}", book.name, chapter.number);
+                let count = chapter.verses.len();
+                if longest_chapter.as_ref().is_none_or(|(_, n)| count > *n) {
+                    longest_chapter = Some((name.clone(), count));
+                }
+                if shortest_chapter.as_ref().is_none_or(|(_, n)| count < *n) {
+                    shortest_chapter = Some((name.clone(), count));
+                }
+                for verse in &chapter.verses {
+                    let count = words(&verse.text).count();
+                    word_count += count;
+                    if longest_verse.as_ref().is_none_or(|(_, n)| count > *n) {
+                        longest_verse = Some((format!("{}:{}", name, verse.number), count));
+                    }
+                }
+            }
+        }
+
+        let title = if name.is_empty() {
+            "Bible"
+        } else {
+            &books[0].name
+        };
+        let mut out = format!("{}\n", title);
+        if name.is_empty() {
+            out.push_str(&format!("  books            {}\n", books.len()));
+        }
+        out.push_str(&format!("  chapters         {}\n", chapters));
+        out.push_str(&format!("  verses           {}\n", verses));
+        out.push_str(&format!("  words            {}\n", word_count));
+        if chapters > 0 {
+            out.push_str(&format!(
+                "  verses/chapter   {:.1}\n",
+                verses as f64 / chapters as f64
+            ));
+        }
+        for (label, extreme, unit) in [
+            ("longest chapter ", longest_chapter, "verse"),
+            ("shortest chapter", shortest_chapter, "verse"),
+            ("longest verse   ", longest_verse, "word"),
+        ] {
+            if let Some((reference, count)) = extreme {
+                out.push_str(&format!(
+                    "  {} {} ({})\n",
+                    label,
+                    reference,
+                    count_of(count, unit)
+                ));
+            }
+        }
+        Ok(out.trim_end().to_string())
+    }
+
+    // Runs one line of input and returns what to print
+    pub fn eval(&mut self, line: &str) -> Result<String, String> {
+        let line = line.trim();
+        let (command, rest) = line.split_once(' ').unwrap_or((line, ""));
+        let rest = rest.trim();
+        match command {
+            "" => Ok(String::new()),
+            "help" => Ok(HELP.to_string()),
+            "books" => Ok(self
+                .books
+                .iter()
+                .map(|b| b.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")),
+            "search" => {
+                let hits = self.bible.query(rest)?;
+                let mut out = count_of(hits.len(), "verse");
+                for hit in hits.iter().take(self.search_limit) {
+                    out.push_str(&format!(
+                        "\n{} {}:{}  {}",
+                        hit.book, hit.chapter, hit.verse.number, hit.verse.text
+                    ));
+                }
+                if hits.len() > self.search_limit {
+                    out.push_str(&format!("\n… {} more", hits.len() - self.search_limit));
+                }
+                Ok(out)
+            }
+            "next" | "prev" => {
+                let current = self
+                    .current
+                    .ok_or("nothing to move on from, look up a passage first")?;
+                let shifted = self
+                    .shift(&current, command == "next")
+                    .ok_or("no more passages this way")?;
+                self.show(shifted)
+            }
+            "stats" => self.stats(rest),
+            _ => {
+                let reference = VerseRef::parse(line).map_err(|e| e.to_string())?;
+                self.show(reference)
+            }
+        }
+    }
+}
+
+// Reads commands until quit or end of input, keeping history across sessions
+pub fn run_repl(bible: &Bible) -> Result<(), Box<dyn Error>> {
+    let history = state_dir().join("repl_history");
+    let mut editor: Editor<ReplHelper, DefaultHistory> = Editor::new()?;
+    editor.set_helper(Some(ReplHelper));
+    // A missing history file just means a first session
+    let _ = editor.load_history(&history);
+
+    let mut repl = Repl::new(bible);
+    println!("Type a reference such as John 3:16, or help");
+    loop {
+        match editor.readline("kjv> ") {
+            Ok(line) => {
+                let line = line.trim();
+                if line.is_empty() {
+                    continue;
+                }
+                editor.add_history_entry(line)?;
+                if line == "quit" || line == "exit" {
+                    break;
+                }
+                match repl.eval(line) {
+                    Ok(out) => println!("{}", out),
+                    Err(e) => eprintln!("error: {}", e),
+                }
+            }
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    std::fs::create_dir_all(state_dir())?;
+    editor.save_history(&history)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::sample_bible;
+
+    #[test]
+    fn shows_passages_and_moves_on() {
+        let bible = sample_bible();
+        let mut repl = Repl::new(&bible);
+
+        let passage = repl.eval("gen 1:1-2").unwrap();
+        assert!(passage.starts_with("Genesis 1:1-2\n  1 In the beginning"));
+        assert_eq!(
+            repl.eval("next").unwrap().lines().next(),
+            Some("Genesis 1:3")
+        );
+        assert_eq!(
+            repl.eval("next").unwrap().lines().next(),
+            Some("Genesis 2:1")
+        );
+        assert_eq!(
+            repl.eval("next").unwrap().lines().next(),
+            Some("Psalms 23:1")
+        );
+        assert_eq!(
+            repl.eval("prev").unwrap().lines().next(),
+            Some("Genesis 2:1")
+        );
+        assert_eq!(
+            repl.eval("prev").unwrap().lines().next(),
+            Some("Genesis 1:3")
+        );
+
+        // Clamped at the start of the chapter
+        repl.eval("Genesis 1:2-3").unwrap();
+        assert_eq!(
+            repl.eval("prev").unwrap().lines().next(),
+            Some("Genesis 1:1")
+        );
+
+        repl.eval("Psalm 23").unwrap();
+        assert_eq!(repl.eval("next").unwrap().lines().next(), Some("John 3"));
+        assert!(repl.eval("next").is_err());
+        assert!(repl.eval("John 4").is_err());
+
+        // Books and chapters without verses do not panic
+        let mut bible = sample_bible();
+        bible.ot[1].chapters.clear();
+        bible.nt[0].chapters[0].verses.clear();
+        let mut repl = Repl::new(&bible);
+        repl.eval("Genesis 2").unwrap();
+        assert!(repl.eval("next").is_err());
+        repl.eval("Genesis 2:1").unwrap();
+        assert!(repl.eval("next").is_err());
+    }
+
+    #[test]
+    fn searches_and_counts() {
+        let bible = sample_bible();
+        let mut repl = Repl::new(&bible);
+
+        let found = repl.eval("search God NEAR/3 heaven").unwrap();
+        assert_eq!(found.lines().next(), Some("1 verse"));
+        assert!(found.contains("Genesis 1:1  In the beginning"));
+        assert!(repl.eval("search light NEAR/").is_err());
+
+        repl.search_limit = 2;
+        let found = repl.eval("search the").unwrap();
+        let lines: Vec<&str> = found.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0], "7 verses");
+        assert!(lines[2].starts_with("Genesis 1:2  "));
+        assert_eq!(lines[3], "… 5 more");
+
+        let stats = repl.eval("stats Gen").unwrap();
+        assert!(stats.starts_with("Genesis\n  chapters         2\n  verses           4\n"));
+        assert!(stats.contains("shortest chapter Genesis 2 (1 verse)"));
+        assert!(repl.eval("stats").unwrap().contains("books            3"));
+        assert!(repl.eval("stats Hezekiah").is_err());
+    }
+
+    #[test]
+    fn completes_books_and_commands() {
+        assert_eq!(complete("sea", 3), (0, vec!["search".to_string()]));
+        assert_eq!(complete("stats Psa", 9), (6, vec!["Psalms".to_string()]));
+        assert_eq!(complete("1 Co", 4), (0, vec!["1 Corinthians".to_string()]));
+        let (start, names) = complete("Jo", 2);
+        assert_eq!(start, 0);
+        assert_eq!(names, vec!["Joshua", "Job", "Joel", "Jonah", "John"]);
+        assert!(complete("John 3:", 7).1.is_empty());
+    }
+}
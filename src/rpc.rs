@@ -0,0 +1,342 @@
+use crate::model::Bible;
+use crate::reference::{ReferenceError, VerseRef, osis_id};
+use serde_json::{Value, json};
+use std::error::Error;
+use std::io::{BufRead, Write};
+
+// JSON-RPC 2.0 error codes
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+// Application error for references that do not resolve
+const REFERENCE_ERROR: i64 = -32000;
+
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        RpcError {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<ReferenceError> for RpcError {
+    fn from(e: ReferenceError) -> Self {
+        RpcError::new(REFERENCE_ERROR, e.to_string())
+    }
+}
+
+// Params may be given by name ({"reference": ...}) or by position ([...])
+fn param<'a>(params: &'a Value, name: &str, index: usize) -> Option<&'a Value> {
+    match params {
+        Value::Object(map) => map.get(name),
+        Value::Array(items) => items.get(index),
+        _ => None,
+    }
+}
+
+fn str_param<'a>(params: &'a Value, name: &str, index: usize) -> Result<&'a str, RpcError> {
+    param(params, name, index)
+        .and_then(Value::as_str)
+        .ok_or_else(|| RpcError::new(INVALID_PARAMS, format!("missing string param '{}'", name)))
+}
+
+fn reference_of(book: &str, chapter: &str, verse: &str) -> String {
+    format!("{} {}:{}", book, chapter, verse)
+}
+
+// Answers requests against one Bible loaded for the lifetime of the process
+pub struct RpcHandler {
+    bible: Bible,
+}
+
+impl RpcHandler {
+    pub fn new(bible: Bible) -> Self {
+        RpcHandler { bible }
+    }
+
+    fn call(&self, method: &str, params: &Value) -> Result<Value, RpcError> {
+        match method {
+            "lookup" => {
+                let reference = VerseRef::parse(str_param(params, "reference", 0)?)?;
+                let verses = self.bible.lookup(&reference)?;
+                Ok(json!({
+                    "reference": reference.to_string(),
+                    "book": reference.book,
+                    "chapter": reference.chapter.to_string(),
+                    "verses": verses,
+                }))
+            }
+            "search" => {
+                let query = str_param(params, "query", 0)?;
+                let limit = match param(params, "limit", 1) {
+                    None | Some(Value::Null) => usize::MAX,
+                    Some(limit) => limit.as_u64().ok_or_else(|| {
+                        RpcError::new(INVALID_PARAMS, "'limit' must be a non-negative integer")
+                    })? as usize,
+                };
+                let hits: Vec<Value> = self
+                    .bible
+                    .search(query)
+                    .into_iter()
+                    .take(limit)
+                    .map(|hit| {
+                        json!({
+                            "reference": reference_of(hit.book, hit.chapter, &hit.verse.number),
+                            "book": hit.book,
+                            "chapter": hit.chapter,
+                            "number": hit.verse.number,
+                            "text": hit.verse.text,
+                        })
+                    })
+                    .collect();
+                Ok(Value::Array(hits))
+            }
+            "books" => {
+                let testament = |books: &[crate::model::Book], name: &str| {
+                    books
+                        .iter()
+                        .map(|b| {
+                            json!({
+                                "name": b.name,
+                                "osis": osis_id(&b.name),
+                                "testament": name,
+                                "chapters": b.chapters.len(),
+                            })
+                        })
+                        .collect::<Vec<_>>()
+                };
+                let mut books = testament(&self.bible.ot, "OT");
+                books.extend(testament(&self.bible.nt, "NT"));
+                Ok(Value::Array(books))
+            }
+            "chapter" => {
+                let book = str_param(params, "book", 0)?;
+                let number = match param(params, "chapter", 1) {
+                    Some(Value::String(s)) => s.clone(),
+                    Some(Value::Number(n)) => n.to_string(),
+                    _ => return Err(RpcError::new(INVALID_PARAMS, "missing param 'chapter'")),
+                };
+                let reference = VerseRef::parse(&format!("{} {}", book, number))?;
+                self.bible.lookup(&reference)?;
+                let chapter = self
+                    .bible
+                    .chapter(reference.book, &reference.chapter.to_string())
+                    .expect("lookup checked the chapter exists");
+                Ok(json!(chapter))
+            }
+            "validate" => {
+                let text = str_param(params, "reference", 0)?;
+                let result = VerseRef::parse(text).and_then(|r| self.bible.lookup(&r).map(|_| r));
+                Ok(match result {
+                    Ok(reference) => json!({"valid": true, "reference": reference.to_string()}),
+                    Err(e) => json!({"valid": false, "error": e.to_string()}),
+                })
+            }
+            _ => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("method '{}' not found", method),
+            )),
+        }
+    }
+
+    // Returns None for notifications, which get no response
+    fn handle_request(&self, request: &Value) -> Option<Value> {
+        // An id, when present, is a string, number or null
+        let id = request.get("id").cloned();
+        let valid_id = matches!(
+            id,
+            None | Some(Value::Null | Value::Number(_) | Value::String(_))
+        );
+        let method = request
+            .get("method")
+            .and_then(Value::as_str)
+            .filter(|_| valid_id && request.get("jsonrpc") == Some(&json!("2.0")));
+        let result = match method {
+            Some(method) => {
+                let params = request.get("params").cloned().unwrap_or(Value::Null);
+                self.call(method, &params)
+            }
+            None => Err(RpcError::new(INVALID_REQUEST, "invalid request")),
+        };
+        // Only well-formed notifications go unanswered; invalid requests are
+        // answered even without an id, with a null id
+        if id.is_none() && method.is_some() {
+            return None;
+        }
+        let id = id.filter(|_| valid_id).unwrap_or(Value::Null);
+        Some(match result {
+            Ok(result) => json!({"jsonrpc": "2.0", "id": id, "result": result}),
+            Err(e) => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": {"code": e.code, "message": e.message},
+            }),
+        })
+    }
+
+    // Handles one line of input, a request or a batch of requests
+    pub fn handle_message(&self, message: &str) -> Option<String> {
+        let response = match serde_json::from_str::<Value>(message) {
+            Err(e) => Some(json!({
+                "jsonrpc": "2.0",
+                "id": null,
+                "error": {"code": PARSE_ERROR, "message": format!("parse error: {}", e)},
+            })),
+            Ok(Value::Array(batch)) if batch.is_empty() => Some(json!({
+                "jsonrpc": "2.0",
+                "id": null,
+                "error": {"code": INVALID_REQUEST, "message": "empty batch"},
+            })),
+            Ok(Value::Array(batch)) => {
+                let responses: Vec<Value> = batch
+                    .iter()
+                    .filter_map(|r| self.handle_request(r))
+                    .collect();
+                (!responses.is_empty()).then_some(Value::Array(responses))
+            }
+            Ok(request) => self.handle_request(&request),
+        };
+        response.map(|r| r.to_string())
+    }
+}
+
+// Reads newline-delimited JSON-RPC messages until end of input
+pub fn run_rpc(
+    bible: Bible,
+    input: impl BufRead,
+    mut output: impl Write,
+) -> Result<(), Box<dyn Error>> {
+    let handler = RpcHandler::new(bible);
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(response) = handler.handle_message(&line) {
+            writeln!(output, "{}", response)?;
+            output.flush()?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::sample_bible;
+
+    fn call(handler: &RpcHandler, message: &str) -> Value {
+        serde_json::from_str(&handler.handle_message(message).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn answers_each_method() {
+        let handler = RpcHandler::new(sample_bible());
+
+        let lookup = call(
+            &handler,
+            r#"{"jsonrpc":"2.0","id":1,"method":"lookup","params":{"reference":"Gen 1:1-2"}}"#,
+        );
+        assert_eq!(lookup["id"], 1);
+        assert_eq!(lookup["result"]["reference"], "Genesis 1:1-2");
+        assert_eq!(lookup["result"]["verses"][1]["number"], "2");
+
+        let search = call(
+            &handler,
+            r#"{"jsonrpc":"2.0","id":2,"method":"search","params":["earth",2]}"#,
+        );
+        assert_eq!(search["result"].as_array().unwrap().len(), 2);
+        assert_eq!(search["result"][0]["reference"], "Genesis 1:1");
+
+        let books = call(&handler, r#"{"jsonrpc":"2.0","id":3,"method":"books"}"#);
+        assert_eq!(books["result"][2]["name"], "John");
+        assert_eq!(books["result"][2]["osis"], "John");
+        assert_eq!(books["result"][2]["testament"], "NT");
+        assert_eq!(books["result"][0]["chapters"], 2);
+
+        let chapter = call(
+            &handler,
+            r#"{"jsonrpc":"2.0","id":"c","method":"chapter","params":{"book":"Ps","chapter":23}}"#,
+        );
+        assert_eq!(chapter["id"], "c");
+        assert_eq!(chapter["result"]["number"], "23");
+        assert_eq!(chapter["result"]["verses"].as_array().unwrap().len(), 2);
+
+        let valid = call(
+            &handler,
+            r#"{"jsonrpc":"2.0","id":5,"method":"validate","params":["Psalm 23:2"]}"#,
+        );
+        assert_eq!(
+            valid["result"],
+            json!({"valid": true, "reference": "Psalms 23:2"})
+        );
+        let invalid = call(
+            &handler,
+            r#"{"jsonrpc":"2.0","id":6,"method":"validate","params":["Psalm 23:9"]}"#,
+        );
+        assert_eq!(invalid["result"]["valid"], false);
+    }
+
+    #[test]
+    fn reports_protocol_errors() {
+        let handler = RpcHandler::new(sample_bible());
+        let code = |message: &str| call(&handler, message)["error"]["code"].clone();
+
+        assert_eq!(code("{not json"), PARSE_ERROR);
+        assert_eq!(code(r#"{"id":1,"method":"books"}"#), INVALID_REQUEST);
+        assert_eq!(code("[]"), INVALID_REQUEST);
+        assert_eq!(
+            code(r#"{"id":[1],"jsonrpc":"2.0","method":"books"}"#),
+            INVALID_REQUEST
+        );
+
+        // Without a valid "jsonrpc" member it is not a notification
+        for message in [
+            r#"{"method":"books"}"#,
+            r#"{"jsonrpc":"1.0","method":"books"}"#,
+        ] {
+            let response = call(&handler, message);
+            assert_eq!(response["error"]["code"], INVALID_REQUEST);
+            assert_eq!(response["id"], Value::Null);
+        }
+        assert_eq!(
+            code(r#"{"jsonrpc":"2.0","id":1,"method":"delete"}"#),
+            METHOD_NOT_FOUND
+        );
+        assert_eq!(
+            code(r#"{"jsonrpc":"2.0","id":1,"method":"lookup","params":{}}"#),
+            INVALID_PARAMS
+        );
+        assert_eq!(
+            code(r#"{"jsonrpc":"2.0","id":1,"method":"lookup","params":["John 9:1"]}"#),
+            REFERENCE_ERROR
+        );
+    }
+
+    #[test]
+    fn handles_batches_and_notifications() {
+        let input = concat!(
+            r#"{"jsonrpc":"2.0","method":"books"}"#,
+            "\n\n",
+            r#"[{"jsonrpc":"2.0","id":1,"method":"books"},{"jsonrpc":"2.0","method":"books"},"#,
+            r#"{"jsonrpc":"2.0","id":2,"method":"lookup","params":["John 3:1"]}]"#,
+            "\n",
+        );
+        let mut output = Vec::new();
+        run_rpc(sample_bible(), input.as_bytes(), &mut output).unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 1);
+        let batch: Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(batch.as_array().unwrap().len(), 2);
+        assert_eq!(batch[1]["result"]["verses"][0]["number"], "1");
+    }
+}
@@ -0,0 +1,171 @@
+use crate::model::{Bible, Verse};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+#[derive(Clone, Copy)]
+pub struct SearchHit<'a> {
+    pub book: &'a str,
+    pub chapter: &'a str,
+    pub verse: &'a Verse,
+}
+
+// Lowercased words of a text, ignoring punctuation such as "light:" or "Herodias’"
+pub fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_lowercase())
+}
+
+impl Bible {
+    // Verses containing every word of the query, case-insensitively, in
+    // canonical order
+    pub fn search(&self, query: &str) -> Vec<SearchHit<'_>> {
+        let terms: Vec<String> = words(query).collect();
+        if terms.is_empty() {
+            return Vec::new();
+        }
+
+        let mut hits = Vec::new();
+        for book in self.books() {
+            for chapter in &book.chapters {
+                for verse in &chapter.verses {
+                    let verse_words: Vec<String> = words(&verse.text).collect();
+                    if terms.iter().all(|t| verse_words.contains(t)) {
+                        hits.push(SearchHit {
+                            book: &book.name,
+                            chapter: &chapter.number,
+                            verse,
+                        });
+                    }
+                }
+            }
+        }
+        hits
+    }
+}
+
+// All words of a query must appear; "grace NEAR/3 faith" additionally
+// requires the two words to be at most 3 words apart
+struct Query {
+    terms: Vec<String>,
+    near: Vec<(usize, usize, usize)>, // indices into terms and the distance
+}
+
+impl Query {
+    fn parse(query: &str) -> Result<Self, String> {
+        let mut terms: Vec<String> = Vec::new();
+        let mut near = Vec::new();
+        let mut pending: Option<usize> = None;
+        for token in query.split_whitespace() {
+            let operator = token
+                .get(..5)
+                .filter(|prefix| prefix.eq_ignore_ascii_case("near/"));
+            if operator.is_some() {
+                let distance = token[5..]
+                    .parse()
+                    .map_err(|_| format!("invalid distance in '{}'", token))?;
+                if terms.is_empty() || pending.is_some() {
+                    return Err(format!("'{}' needs a word on each side", token));
+                }
+                pending = Some(distance);
+                continue;
+            }
+            let before = terms.len();
+            terms.extend(words(token));
+            if let Some(distance) = pending.take() {
+                if terms.len() == before {
+                    return Err("NEAR needs a word on each side".to_string());
+                }
+                near.push((before - 1, before, distance));
+            }
+        }
+        if pending.is_some() {
+            return Err("NEAR needs a word on each side".to_string());
+        }
+        Ok(Query { terms, near })
+    }
+
+    fn matches(&self, text: &str) -> bool {
+        let verse_words: Vec<String> = words(text).collect();
+        let positions = |term: &String| -> Vec<usize> {
+            verse_words
+                .iter()
+                .enumerate()
+                .filter(|(_, w)| *w == term)
+                .map(|(i, _)| i)
+                .collect()
+        };
+        if !self.terms.iter().all(|t| verse_words.contains(t)) {
+            return false;
+        }
+        self.near.iter().all(|&(a, b, distance)| {
+            let (a, b) = (positions(&self.terms[a]), positions(&self.terms[b]));
+            a.iter()
+                .any(|i| b.iter().any(|j| i.abs_diff(*j) <= distance))
+        })
+    }
+}
+
+impl Bible {
+    // Like search, with support for NEAR/n between two words
+    pub fn query(&self, query: &str) -> Result<Vec<SearchHit<'_>>, String> {
+        let query = Query::parse(query)?;
+        let mut hits = Vec::new();
+        if query.terms.is_empty() {
+            return Ok(hits);
+        }
+        for book in self.books() {
+            for chapter in &book.chapters {
+                for verse in &chapter.verses {
+                    if query.matches(&verse.text) {
+                        hits.push(SearchHit {
+                            book: &book.name,
+                            chapter: &chapter.number,
+                            verse,
+                        });
+                    }
+                }
+            }
+        }
+        Ok(hits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::sample_bible;
+
+    #[test]
+    fn finds_verses_containing_all_terms() {
+        let bible = sample_bible();
+
+        let hits = bible.search("LIGHT");
+        assert_eq!(hits.len(), 1);
+        assert_eq!(
+            (hits[0].book, hits[0].chapter, hits[0].verse.number.as_str()),
+            ("Genesis", "1", "3")
+        );
+
+        assert_eq!(bible.search("earth").len(), 3);
+        assert_eq!(bible.search("earth heaven").len(), 1);
+        assert_eq!(bible.search("earth shepherd").len(), 0);
+        assert_eq!(bible.search("eart").len(), 0);
+        assert_eq!(bible.search("  ,. ").len(), 0);
+    }
+
+    #[test]
+    fn finds_words_near_each_other() {
+        let bible = sample_bible();
+
+        // "God created the heaven": heaven is the third word after God
+        assert_eq!(bible.query("God NEAR/3 heaven").unwrap().len(), 1);
+        assert_eq!(bible.query("God near/2 heaven").unwrap().len(), 0);
+        assert_eq!(bible.query("light NEAR/5 God").unwrap().len(), 1);
+        assert_eq!(bible.query("earth").unwrap().len(), 3);
+
+        for invalid in ["NEAR/2 light", "light NEAR/2", "light NEAR/x God"] {
+            assert!(bible.query(invalid).is_err(), "{}", invalid);
+        }
+    }
+}
@@ -0,0 +1,447 @@
+use crate::model::{Bible, Chapter};
+use crate::reference::{VerseRef, resolve_book};
+use serde::Serialize;
+use std::error::Error;
+use std::sync::Arc;
+
+#[derive(Clone, Debug)]
+pub struct ServerOptions {
+    pub addr: String,
+    // Origins allowed to make cross-origin requests, "*" allows any origin
+    pub cors_origins: Vec<String>,
+    pub threads: usize,
+}
+
+impl Default for ServerOptions {
+    fn default() -> Self {
+        ServerOptions {
+            addr: "127.0.0.1:8080".to_string(),
+            cors_origins: Vec::new(),
+            threads: 4,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Response {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+#[derive(Serialize)]
+struct Contents<'a> {
+    ot_contents: &'a [String],
+    nt_contents: &'a [String],
+}
+
+#[derive(Serialize)]
+struct SearchResult<'a> {
+    book: &'a str,
+    chapter: &'a str,
+    number: &'a str,
+    text: &'a str,
+}
+
+#[derive(Serialize)]
+struct ErrorBody {
+    error: String,
+}
+
+// Decodes %XX escapes and '+' in a URL component
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => out.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                match std::str::from_utf8(&bytes[i + 1..i + 3])
+                    .ok()
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                {
+                    Some(byte) => {
+                        out.push(byte);
+                        i += 2;
+                    }
+                    None => out.push(b'%'),
+                }
+            }
+            byte => out.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+// FNV-1a, stable across builds so ETags survive a server restart
+fn etag(body: &[u8]) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in body {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("\"{:016x}\"", hash)
+}
+
+pub struct Server {
+    bible: Bible,
+    options: ServerOptions,
+}
+
+impl Server {
+    pub fn new(bible: Bible, options: ServerOptions) -> Self {
+        Server { bible, options }
+    }
+
+    fn json<T: Serialize>(status: u16, value: &T) -> Response {
+        Response {
+            status,
+            headers: vec![(
+                "Content-Type".to_string(),
+                "application/json; charset=utf-8".to_string(),
+            )],
+            body: serde_json::to_vec(value).expect("response serializes"),
+        }
+    }
+
+    fn error(status: u16, message: impl Into<String>) -> Response {
+        Self::json(
+            status,
+            &ErrorBody {
+                error: message.into(),
+            },
+        )
+    }
+
+    fn route(&self, path: &str, query: Option<&str>) -> Response {
+        let segments: Vec<String> = path
+            .split('/')
+            .filter(|s| !s.is_empty())
+            .map(percent_decode)
+            .collect();
+        let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+
+        match segments.as_slice() {
+            ["books"] => Self::json(
+                200,
+                &Contents {
+                    ot_contents: &self.bible.ot_contents,
+                    nt_contents: &self.bible.nt_contents,
+                },
+            ),
+            ["books", book] => match self.find_book(book) {
+                Some(book) => Self::json(200, book),
+                None => Self::error(404, format!("unknown book '{}'", book)),
+            },
+            ["books", book, "chapters", number] => {
+                match self.find_book(book).and_then(|b| b.chapter(number)) {
+                    Some(chapter) => Self::json(200, chapter),
+                    None => Self::error(404, format!("no chapter {} in '{}'", number, book)),
+                }
+            }
+            ["verses", reference] => self.verses(reference),
+            ["search"] => {
+                let q = query
+                    .into_iter()
+                    .flat_map(|q| q.split('&'))
+                    .find_map(|pair| pair.strip_prefix("q="))
+                    .map(percent_decode)
+                    .unwrap_or_default();
+                if q.trim().is_empty() {
+                    return Self::error(400, "missing query parameter 'q'");
+                }
+                let results: Vec<SearchResult> = self
+                    .bible
+                    .search(&q)
+                    .into_iter()
+                    .map(|hit| SearchResult {
+                        book: hit.book,
+                        chapter: hit.chapter,
+                        number: &hit.verse.number,
+                        text: &hit.verse.text,
+                    })
+                    .collect();
+                Self::json(200, &results)
+            }
+            _ => Self::error(404, "not found"),
+        }
+    }
+
+    fn find_book(&self, name: &str) -> Option<&crate::model::Book> {
+        self.bible.book(resolve_book(name).unwrap_or(name))
+    }
+
+    // A single verse is returned as a Verse, anything longer as a Chapter
+    // holding just the requested verses
+    fn verses(&self, reference: &str) -> Response {
+        let parsed = match VerseRef::parse(reference) {
+            Ok(parsed) => parsed,
+            Err(e) => return Self::error(400, e.to_string()),
+        };
+        match self.bible.lookup(&parsed) {
+            Ok(verses) if parsed.is_single_verse() => Self::json(200, verses[0]),
+            Ok(verses) => Self::json(
+                200,
+                &Chapter {
+                    number: parsed.chapter.to_string(),
+                    verses: verses.into_iter().cloned().collect(),
+                },
+            ),
+            Err(e) => Self::error(404, e.to_string()),
+        }
+    }
+
+    fn cors_headers(&self, origin: Option<&str>) -> Vec<(String, String)> {
+        let allowed = if self.options.cors_origins.iter().any(|o| o == "*") {
+            Some("*".to_string())
+        } else {
+            origin
+                .filter(|o| self.options.cors_origins.iter().any(|allowed| allowed == o))
+                .map(str::to_string)
+        };
+        match allowed {
+            Some(allowed) => vec![
+                ("Access-Control-Allow-Origin".to_string(), allowed),
+                ("Vary".to_string(), "Origin".to_string()),
+            ],
+            None => Vec::new(),
+        }
+    }
+
+    // Handles one request; kept free of any networking so it can be tested
+    pub fn handle(&self, method: &str, url: &str, headers: &[(String, String)]) -> Response {
+        let header = |name: &str| {
+            headers
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case(name))
+                .map(|(_, v)| v.as_str())
+        };
+        let cors = self.cors_headers(header("Origin"));
+
+        let mut response = match method {
+            "OPTIONS" => {
+                let mut headers = cors.clone();
+                if !headers.is_empty() {
+                    headers.push((
+                        "Access-Control-Allow-Methods".to_string(),
+                        "GET, HEAD, OPTIONS".to_string(),
+                    ));
+                    headers.push((
+                        "Access-Control-Allow-Headers".to_string(),
+                        "If-None-Match, Content-Type".to_string(),
+                    ));
+                }
+                return Response {
+                    status: 204,
+                    headers,
+                    body: Vec::new(),
+                };
+            }
+            "GET" | "HEAD" => {
+                let (path, query) = match url.split_once('?') {
+                    Some((path, query)) => (path, Some(query)),
+                    None => (url, None),
+                };
+                self.route(path, query)
+            }
+            _ => Self::error(405, format!("method {} not allowed", method)),
+        };
+        response.headers.extend(cors);
+
+        if response.status == 200 {
+            let tag = etag(&response.body);
+            let fresh = header("If-None-Match")
+                .is_some_and(|v| v.split(',').any(|t| t.trim() == tag || t.trim() == "*"));
+            response.headers.push((
+                "Cache-Control".to_string(),
+                "public, max-age=3600".to_string(),
+            ));
+            response.headers.push(("ETag".to_string(), tag));
+            if fresh {
+                response.status = 304;
+                response.body.clear();
+                response.headers.retain(|(k, _)| k != "Content-Type");
+            }
+        }
+        if method == "HEAD" {
+            response.body.clear();
+        }
+        response
+    }
+}
+
+pub fn serve(bible: Bible, options: ServerOptions) -> Result<(), Box<dyn Error>> {
+    let http = Arc::new(tiny_http::Server::http(&options.addr).map_err(|e| e.to_string())?);
+    let threads = options.threads.max(1);
+    eprintln!(
+        "Serving on http://{} with {} threads",
+        options.addr, threads
+    );
+    let server = Arc::new(Server::new(bible, options));
+
+    let workers: Vec<_> = (0..threads)
+        .map(|_| {
+            let http = Arc::clone(&http);
+            let server = Arc::clone(&server);
+            std::thread::spawn(move || {
+                while let Ok(request) = http.recv() {
+                    let headers: Vec<(String, String)> = request
+                        .headers()
+                        .iter()
+                        .map(|h| (h.field.to_string(), h.value.to_string()))
+                        .collect();
+                    let method = request.method().to_string().to_uppercase();
+                    let response = server.handle(&method, request.url(), &headers);
+
+                    let mut reply = tiny_http::Response::from_data(response.body)
+                        .with_status_code(response.status);
+                    for (name, value) in response.headers {
+                        if let Ok(header) = tiny_http::Header::from_bytes(name, value) {
+                            reply.add_header(header);
+                        }
+                    }
+                    if let Err(e) = request.respond(reply) {
+                        eprintln!("Failed to send response: {}", e);
+                    }
+                }
+            })
+        })
+        .collect();
+
+    for worker in workers {
+        worker.join().map_err(|_| "server thread panicked")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::sample_bible;
+    use serde_json::Value;
+
+    fn get(server: &Server, url: &str) -> (u16, Value) {
+        let response = server.handle("GET", url, &[]);
+        let body = serde_json::from_slice(&response.body).unwrap_or(Value::Null);
+        (response.status, body)
+    }
+
+    fn header<'a>(response: &'a Response, name: &str) -> Option<&'a str> {
+        response
+            .headers
+            .iter()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    #[test]
+    fn serves_books_chapters_and_verses() {
+        let server = Server::new(sample_bible(), ServerOptions::default());
+
+        let (status, books) = get(&server, "/books");
+        assert_eq!(status, 200);
+        assert_eq!(books["ot_contents"][1], "Psalms");
+        assert_eq!(books["nt_contents"][0], "John");
+
+        let (status, chapter) = get(&server, "/books/Ps/chapters/23");
+        assert_eq!(status, 200);
+        assert_eq!(chapter["number"], "23");
+        assert_eq!(
+            chapter["verses"][0]["text"],
+            "The LORD is my shepherd; I shall not want."
+        );
+
+        let (status, verse) = get(&server, "/verses/Genesis%201:3");
+        assert_eq!(status, 200);
+        assert_eq!(verse["number"], "3");
+        assert!(
+            verse["text"]
+                .as_str()
+                .unwrap()
+                .contains("Let there be light")
+        );
+
+        let (status, passage) = get(&server, "/verses/Gen+1:1-2");
+        assert_eq!(status, 200);
+        assert_eq!(passage["verses"].as_array().unwrap().len(), 2);
+
+        let (status, book) = get(&server, "/books/John");
+        assert_eq!((status, book["name"].as_str()), (200, Some("John")));
+    }
+
+    #[test]
+    fn reports_errors_as_json() {
+        let server = Server::new(sample_bible(), ServerOptions::default());
+
+        let (status, body) = get(&server, "/verses/Genesis%209:1");
+        assert_eq!(status, 404);
+        assert_eq!(
+            body["error"],
+            "Genesis has 2 chapters, there is no chapter 9"
+        );
+        assert_eq!(get(&server, "/verses/nonsense").0, 400);
+        assert_eq!(get(&server, "/books/Exodus").0, 404);
+        assert_eq!(get(&server, "/search").0, 400);
+        assert_eq!(get(&server, "/nowhere").0, 404);
+        assert_eq!(server.handle("POST", "/books", &[]).status, 405);
+    }
+
+    #[test]
+    fn searches() {
+        let server = Server::new(sample_bible(), ServerOptions::default());
+        let (status, results) = get(&server, "/search?q=still+waters");
+        assert_eq!(status, 200);
+        assert_eq!(results[0]["book"], "Psalms");
+        assert_eq!(results[0]["number"], "2");
+    }
+
+    #[test]
+    fn answers_conditional_requests_with_304() {
+        let server = Server::new(sample_bible(), ServerOptions::default());
+        let first = server.handle("GET", "/books", &[]);
+        let tag = header(&first, "ETag").unwrap().to_string();
+
+        let cached = server.handle("GET", "/books", &[("if-none-match".to_string(), tag)]);
+        assert_eq!(cached.status, 304);
+        assert!(cached.body.is_empty());
+
+        let stale = server.handle(
+            "GET",
+            "/books",
+            &[("If-None-Match".to_string(), "\"0\"".to_string())],
+        );
+        assert_eq!(stale.status, 200);
+    }
+
+    #[test]
+    fn adds_cors_headers_for_allowed_origins() {
+        let options = ServerOptions {
+            cors_origins: vec!["https://reader.example".to_string()],
+            ..ServerOptions::default()
+        };
+        let server = Server::new(sample_bible(), options);
+        let origin = |o: &str| vec![("Origin".to_string(), o.to_string())];
+
+        let allowed = server.handle("GET", "/books", &origin("https://reader.example"));
+        assert_eq!(
+            header(&allowed, "Access-Control-Allow-Origin"),
+            Some("https://reader.example")
+        );
+        let denied = server.handle("GET", "/books", &origin("https://other.example"));
+        assert_eq!(header(&denied, "Access-Control-Allow-Origin"), None);
+
+        let preflight = server.handle("OPTIONS", "/books", &origin("https://reader.example"));
+        assert_eq!(preflight.status, 204);
+        assert!(header(&preflight, "Access-Control-Allow-Methods").is_some());
+    }
+
+    #[test]
+    fn decodes_url_components() {
+        assert_eq!(percent_decode("John%203%3A16"), "John 3:16");
+        assert_eq!(percent_decode("still+waters"), "still waters");
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("%zz"), "%zz");
+    }
+}
@@ -0,0 +1,289 @@
+use crate::model::{Bible, Book, Chapter};
+use serde_json::json;
+use std::error::Error;
+use std::fmt::Write as _;
+use std::path::Path;
+
+// Page layout. "{{title}}" is the page title, "{{root}}" the relative path
+// back to the site root ("" or "../") and "{{content}}" the page body.
+pub const DEFAULT_TEMPLATE: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{{title}}</title>
+<style>
+body { max-width: 40em; margin: 0 auto; padding: 1em; font: 1.1em/1.6 Georgia, serif; }
+nav { display: flex; justify-content: space-between; margin: 1em 0; }
+sup a { color: #888; text-decoration: none; }
+:target { background: #ffc; }
+</style>
+</head>
+<body>
+<header><a href="{{root}}index.html">Home</a> · <a href="{{root}}search.html">Search</a></header>
+{{content}}
+</body>
+</html>
+"#;
+
+// Loads search-index.json and lists the verses containing every word typed
+const SEARCH_CONTENT: &str = r#"<h1>Search</h1>
+<input id="query" type="search" placeholder="Words to find" autofocus>
+<ol id="results"></ol>
+<script>
+fetch("search-index.json").then(r => r.json()).then(verses => {
+  const query = document.getElementById("query");
+  const results = document.getElementById("results");
+  query.addEventListener("input", () => {
+    const words = query.value.toLowerCase().split(/\s+/).filter(w => w);
+    results.replaceChildren();
+    if (!words.length) return;
+    for (const v of verses.filter(v => words.every(w => v.t.toLowerCase().includes(w))).slice(0, 200)) {
+      const item = document.createElement("li");
+      const link = document.createElement("a");
+      link.href = v.u;
+      link.textContent = v.r;
+      item.append(link, " " + v.t);
+      results.append(item);
+    }
+  });
+});
+</script>
+"#;
+
+#[derive(Clone, Debug)]
+pub struct SiteOptions {
+    pub title: String,
+    pub template: String,
+}
+
+impl Default for SiteOptions {
+    fn default() -> Self {
+        SiteOptions {
+            title: "King James Bible".to_string(),
+            template: DEFAULT_TEMPLATE.to_string(),
+        }
+    }
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// Directory name of a book, "1 Corinthians" -> "1-corinthians"
+fn slug(name: &str) -> String {
+    name.to_lowercase().replace(' ', "-")
+}
+
+fn chapter_path(book: &Book, chapter: &Chapter) -> String {
+    format!("{}/{}.html", slug(&book.name), chapter.number)
+}
+
+fn render_page(options: &SiteOptions, title: &str, root: &str, content: &str) -> String {
+    // Content last, so placeholders in the verse text are left alone
+    options
+        .template
+        .replace("{{title}}", &escape_html(title))
+        .replace("{{root}}", root)
+        .replace("{{content}}", content)
+}
+
+fn render_index(bible: &Bible, options: &SiteOptions) -> String {
+    let mut content = format!("<h1>{}</h1>\n", escape_html(&options.title));
+    for (heading, contents) in [
+        ("Old Testament", &bible.ot_contents),
+        ("New Testament", &bible.nt_contents),
+    ] {
+        writeln!(content, "<h2>{}</h2>\n<ul>", heading).unwrap();
+        for name in contents {
+            // Table of contents entries without a parsed book are listed unlinked
+            match bible.book(name) {
+                Some(book) => writeln!(
+                    content,
+                    "<li><a href=\"{}/index.html\">{}</a></li>",
+                    slug(&book.name),
+                    escape_html(name)
+                )
+                .unwrap(),
+                None => writeln!(content, "<li>{}</li>", escape_html(name)).unwrap(),
+            }
+        }
+        content.push_str("</ul>\n");
+    }
+    render_page(options, &options.title, "", &content)
+}
+
+fn render_book(book: &Book, options: &SiteOptions) -> String {
+    let mut content = format!("<h1>{}</h1>\n<ul>\n", escape_html(&book.name));
+    for chapter in &book.chapters {
+        writeln!(
+            content,
+            "<li><a href=\"{}.html\">Chapter {}</a></li>",
+            chapter.number, chapter.number
+        )
+        .unwrap();
+    }
+    content.push_str("</ul>\n");
+    render_page(options, &book.name, "../", &content)
+}
+
+fn chapter_link(rel: &str, neighbour: Option<(&Book, &Chapter)>) -> String {
+    match neighbour {
+        Some((book, chapter)) => format!(
+            "<a rel=\"{}\" href=\"../{}\">{} {}</a>",
+            rel,
+            chapter_path(book, chapter),
+            escape_html(&book.name),
+            chapter.number
+        ),
+        None => "<span></span>".to_string(),
+    }
+}
+
+fn render_chapter(
+    book: &Book,
+    chapter: &Chapter,
+    prev: Option<(&Book, &Chapter)>,
+    next: Option<(&Book, &Chapter)>,
+    options: &SiteOptions,
+) -> String {
+    let title = format!("{} {}", book.name, chapter.number);
+    let nav = format!(
+        "<nav>{}{}</nav>\n",
+        chapter_link("prev", prev),
+        chapter_link("next", next)
+    );
+    let mut content = format!("<h1>{}</h1>\n{}", escape_html(&title), nav);
+    for verse in &chapter.verses {
+        writeln!(
+            content,
+            "<p id=\"v{}\"><sup><a href=\"#v{}\">{}</a></sup> {}</p>",
+            verse.number,
+            verse.number,
+            verse.number,
+            escape_html(&verse.text)
+        )
+        .unwrap();
+    }
+    content.push_str(&nav);
+    render_page(options, &title, "../", &content)
+}
+
+// Every verse with its reference and page URL, for the search page
+fn search_index(bible: &Bible) -> String {
+    let mut entries = Vec::new();
+    for book in bible.books() {
+        for chapter in &book.chapters {
+            for verse in &chapter.verses {
+                entries.push(json!({
+                    "r": format!("{} {}:{}", book.name, chapter.number, verse.number),
+                    "u": format!("{}#v{}", chapter_path(book, chapter), verse.number),
+                    "t": verse.text,
+                }));
+            }
+        }
+    }
+    serde_json::Value::Array(entries).to_string()
+}
+
+// All files of the site as (relative path, contents)
+pub fn render_site(bible: &Bible, options: &SiteOptions) -> Vec<(String, String)> {
+    let mut files = vec![
+        ("index.html".to_string(), render_index(bible, options)),
+        (
+            "search.html".to_string(),
+            render_page(options, "Search", "", SEARCH_CONTENT),
+        ),
+        ("search-index.json".to_string(), search_index(bible)),
+    ];
+
+    let chapters: Vec<(&Book, &Chapter)> = bible
+        .books()
+        .flat_map(|book| book.chapters.iter().map(move |chapter| (book, chapter)))
+        .collect();
+    for book in bible.books() {
+        files.push((
+            format!("{}/index.html", slug(&book.name)),
+            render_book(book, options),
+        ));
+    }
+    for (i, &(book, chapter)) in chapters.iter().enumerate() {
+        let prev = i.checked_sub(1).map(|p| chapters[p]);
+        let next = chapters.get(i + 1).copied();
+        files.push((
+            chapter_path(book, chapter),
+            render_chapter(book, chapter, prev, next, options),
+        ));
+    }
+    files
+}
+
+pub fn write_site(bible: &Bible, dir: &Path, options: &SiteOptions) -> Result<(), Box<dyn Error>> {
+    for (path, contents) in render_site(bible, options) {
+        let path = dir.join(path);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, contents)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::sample_bible;
+
+    fn file<'a>(files: &'a [(String, String)], path: &str) -> &'a str {
+        &files.iter().find(|(p, _)| p == path).unwrap().1
+    }
+
+    #[test]
+    fn renders_index_and_chapters() {
+        let bible = sample_bible();
+        let files = render_site(&bible, &SiteOptions::default());
+        // index, search page, search index, 3 books and 4 chapters
+        assert_eq!(files.len(), 10);
+
+        let index = file(&files, "index.html");
+        assert!(index.contains(
+            "<h2>Old Testament</h2>\n<ul>\n<li><a href=\"genesis/index.html\">Genesis</a></li>"
+        ));
+        assert!(index.contains(
+            "<h2>New Testament</h2>\n<ul>\n<li><a href=\"john/index.html\">John</a></li>"
+        ));
+
+        let chapter = file(&files, "genesis/2.html");
+        assert!(chapter.contains("<title>Genesis 2</title>"));
+        assert!(chapter.contains("<a rel=\"prev\" href=\"../genesis/1.html\">Genesis 1</a>"));
+        assert!(chapter.contains("<a rel=\"next\" href=\"../psalms/23.html\">Psalms 23</a>"));
+        assert!(chapter.contains("<p id=\"v1\"><sup><a href=\"#v1\">1</a></sup> Thus the heavens"));
+        assert!(chapter.contains("<a href=\"../search.html\">Search</a>"));
+        assert!(file(&files, "genesis/1.html").contains("<nav><span></span><a rel=\"next\""));
+        assert!(file(&files, "john/3.html").contains("</a><span></span></nav>"));
+
+        let index: serde_json::Value =
+            serde_json::from_str(file(&files, "search-index.json")).unwrap();
+        assert_eq!(index.as_array().unwrap().len(), 8);
+        assert_eq!(index[5]["r"], "Psalms 23:2");
+        assert_eq!(index[5]["u"], "psalms/23.html#v2");
+    }
+
+    #[test]
+    fn uses_custom_templates() {
+        let mut bible = sample_bible();
+        bible.nt[0].chapters[0].verses[0].text = "<b> & {{title}}".to_string();
+        let options = SiteOptions {
+            title: "KJV".to_string(),
+            template: "[{{title}}|{{root}}]{{content}}".to_string(),
+        };
+        let files = render_site(&bible, &options);
+        assert!(file(&files, "index.html").starts_with("[KJV|]<h1>KJV</h1>"));
+        let chapter = file(&files, "john/3.html");
+        assert!(chapter.starts_with("[John 3|../]<h1>John 3</h1>"));
+        assert!(chapter.contains("</sup> &lt;b&gt; &amp; {{title}}</p>"));
+    }
+}
@@ -0,0 +1,218 @@
+use crate::model::{Bible, Book, Chapter, Verse};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt::Write as _;
+use std::path::Path;
+
+// Plain text layout meant to be committed to git, so that diffs between
+// Gutenberg releases show up verse by verse:
+//
+//   manifest.json
+//   01-Genesis/001.txt    "1 In the beginning God created..." one verse per line
+//   46-1-Corinthians/013.txt
+const MANIFEST: &str = "manifest.json";
+
+#[derive(Serialize, Deserialize)]
+struct ManifestBook {
+    directory: String,
+    name: String,
+    testament: String, // "ot" or "nt"
+    chapters: usize,
+    verses: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Manifest {
+    ot_contents: Vec<String>,
+    nt_contents: Vec<String>,
+    books: Vec<ManifestBook>,
+}
+
+fn book_directory(index: usize, book: &Book) -> String {
+    format!("{:02}-{}", index + 1, book.name.replace(' ', "-"))
+}
+
+fn chapter_file(chapter: &Chapter) -> Result<String, Box<dyn Error>> {
+    let number: u32 = chapter
+        .number
+        .parse()
+        .map_err(|_| format!("chapter number '{}' is not numeric", chapter.number))?;
+    Ok(format!("{:03}.txt", number))
+}
+
+fn chapter_text(chapter: &Chapter) -> String {
+    let mut out = String::new();
+    for verse in &chapter.verses {
+        writeln!(out, "{} {}", verse.number, verse.text).unwrap();
+    }
+    out
+}
+
+// Writes the tree under dir. Files are only ever added or overwritten, so
+// export into an empty directory (or a clean git checkout) to pick up
+// removed chapters.
+pub fn write_text_tree(bible: &Bible, dir: &Path) -> Result<(), Box<dyn Error>> {
+    let mut manifest = Manifest {
+        ot_contents: bible.ot_contents.clone(),
+        nt_contents: bible.nt_contents.clone(),
+        books: Vec::new(),
+    };
+    let testaments = bible
+        .ot
+        .iter()
+        .map(|book| ("ot", book))
+        .chain(bible.nt.iter().map(|book| ("nt", book)));
+    for (index, (testament, book)) in testaments.enumerate() {
+        let directory = book_directory(index, book);
+        std::fs::create_dir_all(dir.join(&directory))?;
+        for chapter in &book.chapters {
+            let path = dir.join(&directory).join(chapter_file(chapter)?);
+            std::fs::write(path, chapter_text(chapter))?;
+        }
+        manifest.books.push(ManifestBook {
+            directory,
+            name: book.name.clone(),
+            testament: testament.to_string(),
+            chapters: book.chapters.len(),
+            verses: book.chapters.iter().map(|c| c.verses.len()).sum(),
+        });
+    }
+
+    let mut json = serde_json::to_string_pretty(&manifest)?;
+    json.push('\n');
+    std::fs::write(dir.join(MANIFEST), json)?;
+    Ok(())
+}
+
+fn parse_chapter(number: String, text: &str, path: &Path) -> Result<Chapter, Box<dyn Error>> {
+    let mut verses = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let (number, text) = line
+            .split_once(' ')
+            .filter(|(n, _)| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+            .ok_or_else(|| {
+                format!(
+                    "{} line {}: expected a verse number and text",
+                    path.display(),
+                    i + 1
+                )
+            })?;
+        verses.push(Verse {
+            number: number.to_string(),
+            text: text.to_string(),
+        });
+    }
+    Ok(Chapter { number, verses })
+}
+
+fn read_book(dir: &Path, entry: &ManifestBook) -> Result<Book, Box<dyn Error>> {
+    let book_dir = dir.join(&entry.directory);
+    let mut files = Vec::new();
+    for file in std::fs::read_dir(&book_dir)? {
+        let path = file?.path();
+        let number = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| stem.parse::<u32>().ok());
+        if let (Some(number), Some("txt")) = (number, path.extension().and_then(|e| e.to_str())) {
+            files.push((number, path));
+        }
+    }
+    files.sort();
+
+    let mut chapters = Vec::new();
+    for (number, path) in files {
+        let text = std::fs::read_to_string(&path)?;
+        chapters.push(parse_chapter(number.to_string(), &text, &path)?);
+    }
+
+    let verses: usize = chapters.iter().map(|c| c.verses.len()).sum();
+    if chapters.len() != entry.chapters || verses != entry.verses {
+        return Err(format!(
+            "{}: manifest lists {} chapters and {} verses, found {} and {}",
+            entry.directory,
+            entry.chapters,
+            entry.verses,
+            chapters.len(),
+            verses
+        )
+        .into());
+    }
+    Ok(Book {
+        name: entry.name.clone(),
+        chapters,
+    })
+}
+
+// Reads a tree written by write_text_tree, checking it against the manifest
+pub fn read_text_tree(dir: &Path) -> Result<Bible, Box<dyn Error>> {
+    let manifest: Manifest = serde_json::from_slice(&std::fs::read(dir.join(MANIFEST))?)?;
+    let mut bible = Bible {
+        ot_contents: manifest.ot_contents,
+        ot: Vec::new(),
+        nt_contents: manifest.nt_contents,
+        nt: Vec::new(),
+    };
+    for entry in &manifest.books {
+        let book = read_book(dir, entry)?;
+        match entry.testament.as_str() {
+            "ot" => bible.ot.push(book),
+            "nt" => bible.nt.push(book),
+            other => {
+                return Err(format!("{}: unknown testament '{}'", entry.directory, other).into());
+            }
+        }
+    }
+    Ok(bible)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::sample_bible;
+
+    #[test]
+    fn round_trips_through_the_tree() {
+        let bible = sample_bible();
+        let dir = std::env::temp_dir().join(format!("kjv-tree-{}", std::process::id()));
+        write_text_tree(&bible, &dir).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(dir.join("02-Psalms/023.txt")).unwrap(),
+            "1 The LORD is my shepherd; I shall not want.\n\
+             2 He maketh me to lie down in green pastures: he leadeth me beside the still waters.\n"
+        );
+        let manifest = std::fs::read_to_string(dir.join(MANIFEST)).unwrap();
+        assert!(manifest.contains("\"directory\": \"03-John\""));
+        assert!(manifest.ends_with("}\n"));
+
+        let loaded = read_text_tree(&dir).unwrap();
+        assert_eq!(
+            serde_json::to_value(&loaded).unwrap(),
+            serde_json::to_value(&bible).unwrap()
+        );
+
+        // Rewriting gives byte-identical files
+        write_text_tree(&loaded, &dir).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(dir.join(MANIFEST)).unwrap(),
+            manifest
+        );
+
+        std::fs::write(dir.join("01-Genesis/002.txt"), "").unwrap();
+        let err = read_text_tree(&dir).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "01-Genesis: manifest lists 2 chapters and 4 verses, found 2 and 3"
+        );
+
+        std::fs::write(dir.join("01-Genesis/002.txt"), "Thus the heavens\n").unwrap();
+        let err = read_text_tree(&dir).err().unwrap();
+        assert!(
+            err.to_string()
+                .ends_with("002.txt line 1: expected a verse number and text")
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}